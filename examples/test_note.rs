@@ -1,5 +1,5 @@
 use chrono::Utc;
-use noteorg::note::{Note, NoteMetadata, read_note, read_note_metadata};
+use noteorg::note::{Note, TitleSource, read_note};
 use noteorg::traversal::get_files;
 use std::{io, path::Path};
 
@@ -27,7 +27,15 @@ fn main() -> io::Result<()> {
     let note_vec: Vec<Note<Utc>> = files
         .unwrap_or_default()
         .iter()
-        .map(|file| read_note(file, Path::new("./tests/dir_structure_example/"), &Utc).unwrap())
+        .map(|file| {
+            read_note(
+                file,
+                Path::new("./tests/dir_structure_example/"),
+                &Utc,
+                &TitleSource::default_order(),
+            )
+            .unwrap()
+        })
         .collect();
     for note in note_vec {
         println!("Parsed note {:?}\n\n", note);
@@ -0,0 +1,243 @@
+// BM25 ranking over an inverted index of note fields
+//
+// Notes are split into weighted fields (title, tags, category, filename and
+// body). Terms are lowercased and stemmed, and scores use BM25F: the term
+// frequencies of every field are length-normalized and boosted before the
+// usual BM25 saturation.
+
+use crate::note::Note;
+use chrono::TimeZone;
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+static STEMMER: LazyLock<Stemmer> = LazyLock::new(|| Stemmer::create(Algorithm::English));
+
+/// Searchable fields of a note, from the most to the least important
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Tags,
+    Category,
+    Filename,
+    Body,
+}
+
+impl Field {
+    pub const ALL: [Field; 5] = [
+        Field::Title,
+        Field::Tags,
+        Field::Category,
+        Field::Filename,
+        Field::Body,
+    ];
+
+    pub fn boost(self) -> f64 {
+        match self {
+            Field::Title => 3.0,
+            Field::Tags => 2.5,
+            Field::Category => 2.0,
+            Field::Filename => 1.5,
+            Field::Body => 1.0,
+        }
+    }
+}
+
+/// Split text into lowercase stemmed terms
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| STEMMER.stem(&word.to_lowercase()).into_owned())
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct FieldTerms {
+    counts: HashMap<String, u32>,
+    length: u32,
+}
+
+/// Term frequencies of every field of a note, computed once and cached in
+/// the note index
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DocumentTerms {
+    /// One entry per field, in `Field::ALL` order
+    fields: Vec<FieldTerms>,
+}
+
+impl DocumentTerms {
+    pub fn from_note<Tz: TimeZone>(note: &Note<Tz>) -> Self {
+        let filename = note
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let fields = Field::ALL
+            .iter()
+            .map(|field| {
+                let text = match field {
+                    Field::Title => note.metadata.title.clone(),
+                    Field::Tags => note.metadata.tag_names().join(" "),
+                    Field::Category => note.metadata.category.join(" "),
+                    Field::Filename => filename.clone(),
+                    Field::Body => note.body().to_string(),
+                };
+                let mut terms = FieldTerms::default();
+                for term in tokenize(&text) {
+                    *terms.counts.entry(term).or_default() += 1;
+                    terms.length += 1;
+                }
+                terms
+            })
+            .collect();
+        Self { fields }
+    }
+
+    fn length(&self, field: usize) -> u32 {
+        self.fields.get(field).map_or(0, |terms| terms.length)
+    }
+}
+
+struct Posting {
+    document: usize,
+    field: usize,
+    frequency: u32,
+}
+
+/// Inverted index over a set of documents, identified by their position
+pub struct Ranker {
+    postings: HashMap<String, Vec<Posting>>,
+    lengths: Vec<Vec<u32>>,
+    average_lengths: Vec<f64>,
+}
+
+impl Ranker {
+    pub fn new<'a>(documents: impl IntoIterator<Item = &'a DocumentTerms>) -> Self {
+        let mut postings: HashMap<String, Vec<Posting>> = HashMap::new();
+        let mut lengths = vec![];
+
+        for (document, terms) in documents.into_iter().enumerate() {
+            for (field, field_terms) in terms.fields.iter().enumerate() {
+                for (term, frequency) in &field_terms.counts {
+                    postings.entry(term.clone()).or_default().push(Posting {
+                        document,
+                        field,
+                        frequency: *frequency,
+                    });
+                }
+            }
+            lengths.push(
+                (0..Field::ALL.len())
+                    .map(|field| terms.length(field))
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let count = lengths.len().max(1) as f64;
+        let average_lengths = (0..Field::ALL.len())
+            .map(|field| {
+                let total: u32 = lengths.iter().map(|l: &Vec<u32>| l[field]).sum();
+                (f64::from(total) / count).max(1.0)
+            })
+            .collect();
+
+        Self {
+            postings,
+            lengths,
+            average_lengths,
+        }
+    }
+
+    /// Documents matching at least one query term, with their score, best
+    /// first
+    pub fn rank(&self, query: &str) -> Vec<(usize, f64)> {
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let documents = self.lengths.len() as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+
+        for term in terms {
+            let Some(postings) = self.postings.get(&term) else {
+                continue;
+            };
+            // Boosted, length-normalized frequency of the term per document
+            let mut weights: HashMap<usize, f64> = HashMap::new();
+            for posting in postings {
+                let length = f64::from(self.lengths[posting.document][posting.field]);
+                let norm = 1.0 - B + B * length / self.average_lengths[posting.field];
+                *weights.entry(posting.document).or_default() +=
+                    Field::ALL[posting.field].boost() * f64::from(posting.frequency) / norm;
+            }
+            let frequency = weights.len() as f64;
+            let idf = (1.0 + (documents - frequency + 0.5) / (frequency + 0.5)).ln();
+            for (document, weight) in weights {
+                *scores.entry(document).or_default() += idf * weight / (K1 + weight);
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note;
+
+    fn document(path: &str, content: &str) -> DocumentTerms {
+        DocumentTerms::from_note(&note::test_note(path, content))
+    }
+
+    fn ranked(documents: &[DocumentTerms], query: &str) -> Vec<usize> {
+        let ranker = Ranker::new(documents);
+        ranker.rank(query).into_iter().map(|(document, _)| document).collect()
+    }
+
+    #[test]
+    fn tokenizes_and_stems() {
+        assert_eq!(tokenize("Running, runs! RUN-time"), ["run", "run", "run", "time"]);
+    }
+
+    #[test]
+    fn ranks_only_matching_documents() {
+        let documents = [
+            document("groceries.md", "# Groceries\nmilk and eggs"),
+            document("rust.md", "# Rust notes\nownership and borrowing"),
+            document("garden.md", "# Garden\nwater the plants"),
+        ];
+        assert_eq!(ranked(&documents, "borrowing"), [1]);
+        assert_eq!(ranked(&documents, "nothing"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn more_frequent_and_rarer_terms_rank_higher() {
+        let documents = [
+            document("a.md", "rust is nice"),
+            document("b.md", "rust rust rust is nice"),
+            document("c.md", "rust is fast"),
+        ];
+        assert_eq!(ranked(&documents, "rust")[0], 1);
+        // `fast` appears in one document, `nice` in two
+        assert_eq!(ranked(&documents, "nice fast")[0], 2);
+    }
+
+    #[test]
+    fn fields_are_weighted() {
+        let documents = [
+            document("meeting.md", "about the budget"),
+            document("budget.md", "about the meeting"),
+            document("other.md", "---\ntags: [budget]\n---\nabout the meeting"),
+            document("budget/other.md", "about the meeting"),
+        ];
+        // Title, then tags, then category, then body
+        assert_eq!(ranked(&documents, "budget"), [1, 2, 3, 0]);
+    }
+}
@@ -1,11 +1,16 @@
-use crate::{editor, note, search, traversal};
-use chrono::Utc;
+use crate::config::{Config, MatchMode, ROOT_ENV, Timezone};
+use crate::index::Index;
+use crate::journal::{self, Period};
+use crate::links::{LinkGraph, Mentions};
+use crate::query::Query;
+use crate::{editor, note, prompt, search, stats, tags, template, traversal};
+use chrono::{DateTime, Utc};
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::{generate, Shell as CompletionShell};
 
-use home_dir::HomeDirExt;
+use std::collections::HashMap;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -15,6 +20,14 @@ use std::path::Path;
     long_about = "noteorg - Organize and search your markdown notes with ease.\n\nFeatures:\n  - List all notes with metadata\n  - Search through note content, titles, tags, and categories\n  - Edit notes directly in your editor"
 )]
 pub struct Args {
+    /// Configuration file (default: ~/.config/noteorg/config.toml)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Notes directory used by every command (default: notes root from the config)
+    #[arg(long, global = true, env = ROOT_ENV, value_name = "DIR")]
+    pub root: Option<PathBuf>,
+
     #[command(subcommand)]
     pub cmd: Commands,
 }
@@ -23,23 +36,117 @@ pub struct Args {
 pub enum Commands {
     /// List all notes with their metadata (title, tags, category, date)
     List {
-        /// Path to notes directory (default: ~/Notes/)
+        /// Path to notes directory (default: --root or the config notes root)
         #[arg(help = "Custom path to search for notes")]
         path: Option<String>,
+
+        /// Only list the notes matching this query, e.g. `status:draft`
+        #[arg(short, long, allow_hyphen_values = true)]
+        filter: Option<String>,
     },
 
-    /// Edit notes matching a regex pattern
+    /// Edit notes matching a query
     Edit {
-        /// Regex pattern to search for in note content, title, tags, and filename
-        #[arg(help = "Regular expression to match notes")]
+        /// Query such as `tag:rust -tag:draft` or a regex matched against
+        /// note content, title, tags, category and filename
+        #[arg(help = "Query matching the notes to edit", allow_hyphen_values = true)]
         search_value: Option<String>,
     },
 
     /// Interactive search through all notes with real-time results
-    Search,
+    Search {
+        /// Initial query, editable in the search prompt
+        #[arg(allow_hyphen_values = true)]
+        query: Option<String>,
+
+        /// Matching mode, instead of the configured `search_mode`
+        #[arg(short, long, value_enum)]
+        mode: Option<MatchMode>,
+    },
+
+    /// Full-text search ranked by relevance (BM25)
+    Find {
+        /// Words to look for in titles, tags, categories, filenames and content
+        #[arg(required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Create a new note with frontmatter and open it in your editor
+    New(NewArgs),
+
+    /// Open or create today's journal note
+    Today {
+        /// Create the note without opening the editor
+        #[arg(long)]
+        no_edit: bool,
+    },
+
+    /// Open or create yesterday's journal note
+    Yesterday {
+        /// Create the note without opening the editor
+        #[arg(long)]
+        no_edit: bool,
+    },
+
+    /// Open or create the journal note of a day, or its weekly/monthly rollup
+    Journal {
+        /// Date such as 2026-10-01, -3d, "last friday" or "2 weeks ago" (default: today)
+        #[arg(allow_hyphen_values = true)]
+        date: Option<String>,
+
+        /// Open the weekly rollup linking to the daily notes of that week
+        #[arg(long, conflicts_with = "month")]
+        week: bool,
+
+        /// Open the monthly rollup linking to the daily notes of that month
+        #[arg(long)]
+        month: bool,
+
+        /// Create the note without opening the editor
+        #[arg(long)]
+        no_edit: bool,
+    },
+
+    /// Manage note templates
+    Templates {
+        #[command(subcommand)]
+        action: TemplatesCommand,
+    },
+
+    /// List the tags of your notes with their counts, or rename and merge them
+    Tags {
+        #[command(subcommand)]
+        action: Option<TagsCommand>,
+    },
+
+    /// Add tags to or remove tags from the notes matching a query
+    Tag {
+        #[command(subcommand)]
+        action: TagCommand,
+    },
+
+    /// List the notes linking to the notes matching a query, and the notes
+    /// mentioning them without a link
+    Backlinks {
+        /// Query matching the linked notes, e.g. `file:ideas`
+        #[arg(allow_hyphen_values = true)]
+        query: String,
+    },
 
-    /// Show statistics about your notes (not yet implemented)
-    Statistics,
+    /// Show statistics about your notes (categories, tags, activity)
+    Statistics {
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Only count the notes matching this query, e.g. `cat:work`
+        #[arg(short, long, allow_hyphen_values = true)]
+        filter: Option<String>,
+    },
 
     /// Generate shell completion scripts
     Completions {
@@ -49,6 +156,128 @@ pub enum Commands {
     },
 }
 
+#[derive(clap::Args, Debug, Clone)]
+pub struct NewArgs {
+    /// Title of the note, also used to name the file
+    pub title: String,
+
+    /// Category (subdirectory) of the note, e.g. work/meetings
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// Tag to add to the note (repeatable)
+    #[arg(short, long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Template from ~/.config/noteorg/templates/ used for the body
+    #[arg(long)]
+    pub template: Option<String>,
+
+    /// Value of a template variable (repeatable), prompted for otherwise
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_variable)]
+    pub variables: Vec<(String, String)>,
+
+    /// Create the note without opening the editor
+    #[arg(long)]
+    pub no_edit: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TemplatesCommand {
+    /// List the available templates
+    List,
+
+    /// Print a template
+    Show {
+        /// Template name
+        name: String,
+    },
+
+    /// Create a template from a skeleton and open it in your editor
+    New {
+        /// Template name
+        name: String,
+
+        /// Create the template without opening the editor
+        #[arg(long)]
+        no_edit: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TagsCommand {
+    /// Rename a tag, and the tags nested below it, in every note
+    Rename {
+        /// Current tag name
+        old: String,
+
+        /// New tag name
+        new: String,
+
+        /// Show the changes as a diff without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Merge several tags, and the tags nested below them, into one
+    Merge {
+        /// Tags to merge
+        #[arg(required = true, num_args = 1..)]
+        tags: Vec<String>,
+
+        /// Tag replacing them
+        #[arg(long)]
+        into: String,
+
+        /// Show the changes as a diff without writing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TagCommand {
+    /// Add tags to the frontmatter of the notes matching a query
+    Add {
+        /// Query matching the notes, e.g. `file:ideas` or `cat:work`
+        #[arg(allow_hyphen_values = true)]
+        query: String,
+
+        /// Tags to add
+        #[arg(required = true, num_args = 1..)]
+        tags: Vec<String>,
+
+        /// Change the notes even when the query matches many of them
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Remove tags from the frontmatter of the notes matching a query
+    #[command(alias = "remove")]
+    Rm {
+        /// Query matching the notes, e.g. `tag:draft cat:blog`
+        #[arg(allow_hyphen_values = true)]
+        query: String,
+
+        /// Tags to remove
+        #[arg(required = true, num_args = 1..)]
+        tags: Vec<String>,
+
+        /// Change the notes even when the query matches many of them
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+fn parse_variable(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected NAME=VALUE, got {:?}", value)),
+    }
+}
+
 #[derive(Debug, Clone, ValueEnum)]
 pub enum Shell {
     Bash,
@@ -58,65 +287,85 @@ pub enum Shell {
     Elvish,
 }
 
-pub fn list_files(path: Option<String>) -> io::Result<()> {
+pub fn list_files(path: Option<String>, filter: Option<&str>, config: &Config) -> io::Result<()> {
     let base_path = match path {
-        Some(value) => value,
-        None => "~/Notes/"
-            .expand_home()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string(),
+        Some(value) => PathBuf::from(value),
+        None => config.root()?,
     };
-    list_files_internal(&base_path)
+    list_files_internal(&base_path, filter, config)
 }
 
-fn list_files_internal(base_path: &str) -> io::Result<()> {
-    let files = traversal::get_files(Path::new(base_path));
-    let note_vec: Vec<note::Note<Utc>> = files
-        .unwrap_or_default()
-        .iter()
-        .filter(|&x| x.to_string_lossy().contains(".md"))
-        .map(|file| {
-            note::read_note(file, Path::new("./tests/dir_structure_example/"), &Utc).unwrap()
-        })
-        .collect();
-    for note in note_vec {
-        // Format category as path, filtering out "Notes"
-        let category_parts: Vec<&String> = note.metadata.category
-            .iter()
-            .filter(|&part| part != "Notes")
-            .collect();
-
-        let category = if category_parts.is_empty() {
-            String::from("root")
-        } else {
-            category_parts.iter().map(|s| s.as_str()).collect::<Vec<_>>().join("/")
-        };
-
-        // Format tags
-        let tags = if note.metadata.tags.is_empty() {
-            String::new()
-        } else {
-            format!("#{}",note.metadata.tags.join(" #"))
-        };
+/// Every note below `base_path`, read through the index
+fn load_notes(base_path: &Path, config: &Config) -> io::Result<Vec<note::Note<Timezone>>> {
+    let index = Index::open(base_path, config)?;
+    Ok(index.notes(&config.tz()?))
+}
 
-        // Format date (just the date part, not time)
-        let modified = note.metadata.date_last_modified.format("%Y-%m-%d");
+/// Notes matching the query `filter`, all of them without one
+fn filter_notes(
+    notes: Vec<note::Note<Timezone>>,
+    filter: Option<&str>,
+    config: &Config,
+) -> io::Result<Vec<note::Note<Timezone>>> {
+    let Some(filter) = filter else {
+        return Ok(notes);
+    };
+    let today = Utc::now().with_timezone(&config.tz()?).date_naive();
+    let query = Query::parse(filter, today)?;
+    Ok(notes.into_iter().filter(|note| query.matches(note)).collect())
+}
 
-        // Print in format: [category/path] Title #tags (date)
-        if tags.is_empty() {
-            println!("[{}] {} ({})", category, note.metadata.title, modified);
-        } else {
-            println!("[{}] {} {} ({})", category, note.metadata.title, tags, modified);
-        }
+fn list_files_internal(base_path: &Path, filter: Option<&str>, config: &Config) -> io::Result<()> {
+    for note in filter_notes(load_notes(base_path, config)?, filter, config)? {
+        println!("{}", format_note_line(&note, &config.list_format));
     }
     Ok(())
 }
 
-pub fn edit_file(search_value: Option<String>) -> io::Result<()> {
+/// Render a note with the `list_format` template. Supported placeholders are
+/// `{category}`, `{title}`, `{tags}`, `{modified}`, `{created}`, `{filename}`,
+/// `{path}`, `{status}`, `{id}` and `{aliases}`.
+fn format_note_line(note: &note::Note<Timezone>, format: &str) -> String {
+    // Format category as path
+    let category = if note.metadata.category.is_empty() {
+        String::from("root")
+    } else {
+        note.metadata.category.join("/")
+    };
+
+    // Format tags, dropping the placeholder and its separator when empty
+    let (format, tags) = if note.metadata.tags.is_empty() {
+        (format.replace(" {tags}", ""), String::new())
+    } else {
+        (
+            format.to_string(),
+            format!("#{}", note.metadata.tag_names().join(" #")),
+        )
+    };
+
+    // Format dates (just the date part, not time)
+    format
+        .replace("{category}", &category)
+        .replace("{title}", &note.metadata.title)
+        .replace("{tags}", &tags)
+        .replace(
+            "{modified}",
+            &note.metadata.date_last_modified.format("%Y-%m-%d").to_string(),
+        )
+        .replace(
+            "{created}",
+            &note.metadata.date_created.format("%Y-%m-%d").to_string(),
+        )
+        .replace("{filename}", &note.metadata.filename)
+        .replace("{path}", &note.path.to_string_lossy())
+        .replace("{status}", note.metadata.status.as_deref().unwrap_or_default())
+        .replace("{id}", note.metadata.id.as_deref().unwrap_or_default())
+        .replace("{aliases}", &note.metadata.aliases.join(", "))
+}
+
+pub fn edit_file(search_value: Option<String>, config: &Config) -> io::Result<()> {
     match search_value {
-        Some(value) => edit_file_internal(&value),
+        Some(value) => edit_file_internal(&value, config),
         None => Err(io::Error::new(
             io::ErrorKind::InvalidInput,
             "Not a valid path",
@@ -124,24 +373,472 @@ pub fn edit_file(search_value: Option<String>) -> io::Result<()> {
     }
 }
 
-fn edit_file_internal(search_value: &str) -> io::Result<()> {
-    let base_path = "~/Notes/".expand_home().unwrap();
-    let matched_files = search::search_files(search_value, base_path.as_path())?;
+fn edit_file_internal(search_value: &str, config: &Config) -> io::Result<()> {
+    let matched_files = search::search_files(search_value, config)?;
 
     if matched_files.is_empty() {
         return Err(io::Error::new(
             io::ErrorKind::NotFound,
-            format!("No file found matching regex: {}", search_value),
+            format!("No note found matching query: {}", search_value),
         ));
     }
 
-    editor::launch_editor(&matched_files)?;
+    let targets: Vec<editor::EditTarget> =
+        matched_files.iter().map(search::SearchMatch::target).collect();
+    editor::launch_editor(&targets, config.editor.as_deref())?;
+    Ok(())
+}
+
+pub fn find_notes(query: &str, limit: usize, config: &Config) -> io::Result<()> {
+    let index = Index::open(&config.root()?, config)?;
+    let notes = index.notes(&config.tz()?);
+    let results = search::rank(query, &notes, &index.ranker());
+
+    if results.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No note found for: {}", query),
+        ));
+    }
+    for result in results.iter().take(limit) {
+        let title = notes
+            .iter()
+            .find(|note| note.path == result.path)
+            .map_or("", |note| note.metadata.title.as_str());
+        let location = match result.line {
+            Some(line) => format!("{}:{}", result.path.display(), line),
+            None => result.path.display().to_string(),
+        };
+        println!("{:>7.2}  {}  {}", result.score, title, location);
+        if !result.snippet.is_empty() {
+            println!("         {}", result.snippet);
+        }
+    }
     Ok(())
 }
 
-pub fn show_search_results_realtime() -> io::Result<()> {
-    let base_path = "~/Notes/".expand_home().unwrap();
-    search::show_search_results_realtime(base_path.as_path())
+pub fn show_search_results_realtime(
+    query: Option<&str>,
+    mode: Option<MatchMode>,
+    config: &Config,
+) -> io::Result<()> {
+    search::show_search_results_realtime(query.unwrap_or_default(), mode, config)
+}
+
+/// Everything needed to write a new note
+struct NoteDraft<'a> {
+    title: String,
+    category: String,
+    tags: Vec<String>,
+    template: Option<&'a template::Template>,
+    variables: HashMap<String, String>,
+    /// Body used when there is no template
+    body: String,
+    /// Date of the note, also used by the template date placeholders
+    date: DateTime<Timezone>,
+}
+
+/// Write a draft at `path` and return where the editor should open it
+fn write_draft(draft: NoteDraft, path: PathBuf) -> io::Result<editor::EditTarget> {
+    let now = draft.date;
+    let date = now.format("%Y-%m-%d").to_string();
+
+    let mut tags = draft.tags;
+    let (body, cursor_line) = match draft.template {
+        Some(template) => {
+            for tag in &template.tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            let mut variables = draft.variables;
+            variables.insert(String::from("title"), draft.title.clone());
+            variables.insert(String::from("category"), draft.category.clone());
+            variables.insert(String::from("uuid"), uuid::Uuid::new_v4().to_string());
+            let rendered = template.render(&template::Context { now, variables })?;
+            (rendered.text, rendered.cursor_line)
+        }
+        None => (draft.body, None),
+    };
+
+    let frontmatter = note::NoteFrontMatter::new(&draft.title, tags, date);
+    note::create_note(&path, &frontmatter, &body)?;
+    println!("Created {}", path.display());
+
+    // Lines before the body: the frontmatter block and a blank line
+    let body_offset = frontmatter.to_block()?.lines().count() + 1;
+    Ok(editor::EditTarget {
+        path,
+        line: Some(body_offset + cursor_line.unwrap_or(1)),
+    })
+}
+
+pub fn new_note(args: &NewArgs, config: &Config) -> io::Result<()> {
+    let template = args
+        .template
+        .as_deref()
+        .map(template::Template::load)
+        .transpose()?;
+    let category = args
+        .category
+        .as_deref()
+        .or(template.as_ref().and_then(|t| t.category.as_deref()))
+        .unwrap_or_default()
+        .trim_matches('/')
+        .to_string();
+
+    let root = config.create_root()?;
+    let extension = config.extensions.first().map_or("md", String::as_str);
+    let path = note::category_dir(&root, &category)?
+        .join(format!("{}.{}", note::slugify(&args.title), extension));
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{:?} already exists", path),
+        ));
+    }
+
+    let provided: HashMap<String, String> = args.variables.iter().cloned().collect();
+    let variables = match &template {
+        Some(template) => template.resolve_variables(&provided, prompt::is_interactive())?,
+        None => provided,
+    };
+
+    let target = write_draft(
+        NoteDraft {
+            title: args.title.clone(),
+            category,
+            tags: args.tags.clone(),
+            template: template.as_ref(),
+            variables,
+            body: String::new(),
+            date: Utc::now().with_timezone(&config.tz()?),
+        },
+        path,
+    )?;
+    if args.no_edit {
+        return Ok(());
+    }
+    editor::launch_editor(&[target], config.editor.as_deref())
+}
+
+pub fn open_journal(date: &str, period: Period, no_edit: bool, config: &Config) -> io::Result<()> {
+    let root = config.create_root()?;
+    let now = Utc::now().with_timezone(&config.tz()?);
+    let date = journal::parse_date(date, now.date_naive())?;
+    let path = journal::note_path(&root, date, period, config);
+
+    let target = if path.is_file() {
+        if period != Period::Day {
+            // Keep rollups up to date with daily notes written since
+            let content = std::fs::read_to_string(&path)?;
+            let links = journal::missing_daily_links(&root, date, period, config, &content);
+            if !links.is_empty() {
+                note::write_note(&path, &journal::add_daily_links(&content, &links))?;
+                println!("Linked {} new daily notes in {}", links.len(), path.display());
+            }
+        }
+        path.into()
+    } else {
+        let template = match (period, &config.journal.template) {
+            (Period::Day, Some(name)) => match template::Template::load(name) {
+                Ok(template) => Some(template),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            },
+            _ => None,
+        };
+        let variables = match &template {
+            Some(template) => {
+                template.resolve_variables(&HashMap::new(), prompt::is_interactive())?
+            }
+            None => HashMap::new(),
+        };
+        let body = match period {
+            Period::Day => String::new(),
+            Period::Week | Period::Month => {
+                let links = journal::missing_daily_links(&root, date, period, config, "");
+                journal::rollup_body(&links)
+            }
+        };
+        write_draft(
+            NoteDraft {
+                title: journal::note_title(date, period, config),
+                category: config.journal.category.clone(),
+                tags: vec![],
+                template: template.as_ref(),
+                variables,
+                body,
+                date: date
+                    .and_time(now.time())
+                    .and_local_timezone(now.timezone())
+                    .single()
+                    .unwrap_or(now),
+            },
+            path,
+        )?
+    };
+
+    if no_edit {
+        return Ok(());
+    }
+    editor::launch_editor(&[target], config.editor.as_deref())
+}
+
+pub fn manage_templates(action: &TemplatesCommand, config: &Config) -> io::Result<()> {
+    match action {
+        TemplatesCommand::List => {
+            let names = template::template_names()?;
+            if names.is_empty() {
+                println!("No templates in {}", template::templates_dir()?.display());
+            }
+            for name in names {
+                // A broken template should not hide the others
+                let template = match template::Template::load(&name) {
+                    Ok(template) => template,
+                    Err(e) => {
+                        println!("{} (invalid: {})", name, e);
+                        continue;
+                    }
+                };
+                let variables: Vec<&str> =
+                    template.variables.iter().map(|v| v.name.as_str()).collect();
+                print!("{}", template.name);
+                if let Some(description) = &template.description {
+                    print!(" - {}", description);
+                }
+                if !variables.is_empty() {
+                    print!(" ({})", variables.join(", "));
+                }
+                println!();
+            }
+            Ok(())
+        }
+        TemplatesCommand::Show { name } => {
+            let template = template::Template::load(name)?;
+            print!("{}", std::fs::read_to_string(&template.path)?);
+            Ok(())
+        }
+        TemplatesCommand::New { name, no_edit } => {
+            let path = template::create_template(name)?;
+            println!("Created {}", path.display());
+            if *no_edit {
+                return Ok(());
+            }
+            editor::launch_editor(&[path.into()], config.editor.as_deref())
+        }
+    }
+}
+
+pub fn manage_tags(action: Option<&TagsCommand>, config: &Config) -> io::Result<()> {
+    let notes = load_notes(&config.root()?, config)?;
+    let (from, to, dry_run) = match action {
+        None => {
+            let tree = tags::TagTree::from_notes(&notes);
+            if tree.children.is_empty() {
+                println!("No tags");
+            } else {
+                tree.labelled("tags").display();
+            }
+            return Ok(());
+        }
+        Some(TagsCommand::Rename { old, new, dry_run }) => (vec![old.clone()], new, *dry_run),
+        Some(TagsCommand::Merge {
+            tags,
+            into,
+            dry_run,
+        }) => (tags.clone(), into, *dry_run),
+    };
+    let from = from
+        .iter()
+        .map(|name| tags::parse_name(name))
+        .collect::<io::Result<Vec<_>>>()?;
+    let to = tags::parse_name(to)?;
+
+    let rewrites = tags::plan_rename(&notes, &from, &to)?;
+    for rewrite in &rewrites {
+        if dry_run {
+            print!("{}", tags::diff(rewrite));
+        } else {
+            // Renamed again in what is on disk now, through the checks of
+            // frontmatter edits
+            note::frontmatter::update(&rewrite.path, |content| {
+                tags::rename_in_content(content, &from, &to).unwrap_or_else(|| content.to_string())
+            })?;
+        }
+    }
+    let from = from
+        .iter()
+        .map(|name| format!("#{}", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let notes = if rewrites.len() == 1 { "note" } else { "notes" };
+    if dry_run {
+        println!("Would rename {} to #{} in {} {}", from, to, rewrites.len(), notes);
+    } else {
+        println!("Renamed {} to #{} in {} {}", from, to, rewrites.len(), notes);
+    }
+    Ok(())
+}
+
+pub fn show_backlinks(query: &str, config: &Config) -> io::Result<()> {
+    let root = config.root()?;
+    let matches = search::search_files(query, config)?;
+    if matches.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No note found matching query: {}", query),
+        ));
+    }
+    let notes = load_notes(&root, config)?;
+    let graph = LinkGraph::build(&notes);
+
+    let relative = |path: &Path| path.strip_prefix(&root).unwrap_or(path).display().to_string();
+    // A line of a note with its location, trimmed
+    let context = |note: &note::Note<Timezone>, line: usize| {
+        let text = note.content.lines().nth(line - 1).unwrap_or_default().trim();
+        println!("  {}:{}  {}", relative(&note.path), line, text);
+    };
+
+    for (i, result) in matches.iter().enumerate() {
+        let Some(target) = graph.index_of(&result.path) else {
+            continue;
+        };
+        let note = &notes[target];
+        if i > 0 {
+            println!();
+        }
+        println!("{} ({})", note.metadata.title, relative(&note.path));
+
+        println!("\nBacklinks:");
+        let mut linked: Vec<(usize, usize)> = graph
+            .backlinks(target)
+            .map(|edge| (edge.source, edge.link.line))
+            .collect();
+        linked.dedup();
+        if linked.is_empty() {
+            println!("  none");
+        }
+        for &(source, line) in &linked {
+            context(&notes[source], line);
+        }
+
+        println!("\nUnlinked mentions:");
+        let mut names = vec![note.metadata.title.clone()];
+        names.extend(note.metadata.aliases.iter().cloned());
+        let mut mentioned = false;
+        if let Some(mentions) = Mentions::new(&names) {
+            for (source, other) in notes.iter().enumerate() {
+                if source == target {
+                    continue;
+                }
+                let links = graph.outgoing(source).map(|edge| &edge.link);
+                for line in mentions.lines(&other.content, links) {
+                    mentioned = true;
+                    context(other, line);
+                }
+            }
+        }
+        if !mentioned {
+            println!("  none");
+        }
+    }
+    Ok(())
+}
+
+/// Number of notes `note tag` changes without `--yes`
+const TAG_LIMIT: usize = 20;
+
+/// Add `names` to, or remove them from, the notes at `paths` matching
+/// `query`, returning the notes that changed. More than `TAG_LIMIT` notes
+/// are only changed with `yes`.
+fn retag<'a>(
+    query: &str,
+    paths: &'a [PathBuf],
+    names: &[String],
+    add: bool,
+    yes: bool,
+) -> io::Result<Vec<&'a PathBuf>> {
+    if paths.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No note found matching query: {}", query),
+        ));
+    }
+    if paths.len() > TAG_LIMIT && !yes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} notes match {:?}, pass --yes to change more than {} notes at once",
+                paths.len(),
+                query,
+                TAG_LIMIT
+            ),
+        ));
+    }
+
+    let mut changed = vec![];
+    for path in paths {
+        let modified = if add {
+            tags::add_tags(path, names)?
+        } else {
+            tags::remove_tags(path, names)?
+        };
+        if modified {
+            changed.push(path);
+        }
+    }
+    Ok(changed)
+}
+
+pub fn tag_notes(action: &TagCommand, config: &Config) -> io::Result<()> {
+    let (query, names, yes, add) = match action {
+        TagCommand::Add { query, tags, yes } => (query, tags, *yes, true),
+        TagCommand::Rm { query, tags, yes } => (query, tags, *yes, false),
+    };
+    let names = names
+        .iter()
+        .map(|name| tags::parse_name(name))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let matches = search::search_files(query, config)?;
+    let paths: Vec<PathBuf> = matches.into_iter().map(|result| result.path).collect();
+    let root = config.root()?;
+    let changed = retag(query, &paths, &names, add, yes)?;
+    for path in &changed {
+        println!("{}", path.strip_prefix(&root).unwrap_or(path).display());
+    }
+
+    let names = names
+        .iter()
+        .map(|name| format!("#{}", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let notes = if paths.len() == 1 { "note" } else { "notes" };
+    if add {
+        println!("Added {} to {} of {} {}", names, changed.len(), paths.len(), notes);
+    } else {
+        println!("Removed {} from {} of {} {}", names, changed.len(), paths.len(), notes);
+    }
+    Ok(())
+}
+
+pub fn show_statistics(json: bool, filter: Option<&str>, config: &Config) -> io::Result<()> {
+    let base_path = config.root()?;
+    let all_notes = load_notes(&base_path, config)?;
+    // Links are resolved against the whole vault, not only the filtered notes
+    let graph = LinkGraph::build(&all_notes);
+    let notes = filter_notes(all_notes, filter, config)?;
+    let tree = traversal::CategoryTree::try_from(base_path.as_path())?;
+    let statistics = stats::compute(&notes, &graph, &tree);
+
+    if json {
+        let output = serde_json::to_string_pretty(&statistics).map_err(io::Error::other)?;
+        println!("{}", output);
+    } else {
+        stats::print_human(&statistics);
+    }
+    Ok(())
 }
 
 pub fn generate_completions(shell: Shell) {
@@ -157,3 +854,46 @@ pub fn generate_completions(shell: Shell) {
 
     generate(completion_shell, &mut cmd, "note", &mut io::stdout());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn rejects_invalid_tags_before_searching() {
+        let action = TagCommand::Add {
+            query: String::from("todo"),
+            tags: vec![String::from("rust"), String::from("two words")],
+            yes: false,
+        };
+        let error = tag_notes(&action, &Config::default()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn retags_matching_notes() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(retag("none", &[], &names(&["a"]), true, false).unwrap_err().kind(), io::ErrorKind::NotFound);
+        // Too many notes are only changed with `--yes`, checked before
+        // changing any
+        let many: Vec<PathBuf> = (0..=TAG_LIMIT).map(|i| PathBuf::from(format!("{}.md", i))).collect();
+        let error = retag("all", &many, &names(&["a"]), true, false).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let dir = std::env::temp_dir().join(format!("noteorg-{}-retag", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths = vec![dir.join("a.md"), dir.join("b.md")];
+        fs::write(&paths[0], "---\ntags: [Rust]\n---\nbody\n").unwrap();
+        fs::write(&paths[1], "body\n").unwrap();
+        let added = retag("q", &paths, &names(&["rust", "ideas"]), true, false).unwrap();
+        assert_eq!(added, [&paths[0], &paths[1]]);
+        let removed = retag("q", &paths, &names(&["RUST", "ideas"]), false, false).unwrap();
+        assert_eq!(removed, [&paths[0], &paths[1]]);
+        assert!(retag("q", &paths, &names(&["ideas"]), false, false).unwrap().is_empty());
+        let contents: Vec<String> = paths.iter().map(|path| fs::read_to_string(path).unwrap()).collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(contents[0], "---\ntags: []\n---\nbody\n");
+        assert_eq!(contents[1], "---\ntags: []\n---\n\nbody\n");
+    }
+}
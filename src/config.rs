@@ -0,0 +1,480 @@
+// Configuration loading and defaults
+
+use crate::note::{self, TitleSource};
+use crate::traversal;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone};
+use glob::Pattern;
+use home_dir::HomeDirExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{create_dir_all, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable pointing to an alternative configuration file
+pub const CONFIG_ENV: &str = "NOTEORG_CONFIG";
+
+/// Environment variable overriding the notes root
+pub const ROOT_ENV: &str = "NOTEORG_ROOT";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Root directory of the notes vault
+    pub notes_root: String,
+    /// Editor command used to open notes, before `$VISUAL` and `$EDITOR`
+    pub editor: Option<String>,
+    /// File extensions (without the dot) considered to be notes
+    pub extensions: Vec<String>,
+    /// Glob patterns, relative to the notes root, of files to skip
+    pub ignore: Vec<String>,
+    /// Timezone used for dates: "utc", "local" or a fixed offset like "+02:00"
+    pub timezone: String,
+    /// Line template used by `note list`
+    pub list_format: String,
+    /// Sources of note titles, in order: "frontmatter", "heading", "filename"
+    pub title_from: Vec<TitleSource>,
+    /// Settings of some vaults, by root directory, overriding the ones above
+    pub vaults: HashMap<String, VaultConfig>,
+    /// Initial matching mode of `note search`: "regex", "fuzzy", "literal"
+    /// or "query"
+    pub search_mode: MatchMode,
+    /// Journal notes (`note today`, `note journal`)
+    pub journal: JournalConfig,
+}
+
+/// How the interactive search interprets its input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// Regular expression, searched in the content and metadata of the notes
+    #[default]
+    Regex,
+    /// Characters in order, not necessarily next to each other (like fzf)
+    Fuzzy,
+    /// Exact substring
+    Literal,
+    /// Query language (`tag:rust`, regex terms, ...)
+    Query,
+}
+
+impl MatchMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            MatchMode::Regex => "regex",
+            MatchMode::Fuzzy => "fuzzy",
+            MatchMode::Literal => "literal",
+            MatchMode::Query => "query",
+        }
+    }
+
+    /// Mode following this one when switching modes
+    pub fn next(self) -> Self {
+        match self {
+            MatchMode::Regex => MatchMode::Fuzzy,
+            MatchMode::Fuzzy => MatchMode::Literal,
+            MatchMode::Literal => MatchMode::Query,
+            MatchMode::Query => MatchMode::Regex,
+        }
+    }
+}
+
+/// Settings of one vault, unset ones taken from the global configuration
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct VaultConfig {
+    /// Sources of note titles, in order
+    pub title_from: Option<Vec<TitleSource>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournalConfig {
+    /// Category holding the journal notes
+    pub category: String,
+    /// Template used for daily notes, skipped when it does not exist
+    pub template: Option<String>,
+    /// strftime format of daily note file names
+    pub filename_format: String,
+    /// strftime format of daily note titles
+    pub title_format: String,
+    /// strftime format of weekly rollup file names
+    pub weekly_format: String,
+    /// strftime format of monthly rollup file names
+    pub monthly_format: String,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            category: String::from("journal"),
+            template: Some(String::from("journal")),
+            filename_format: String::from("%Y-%m-%d"),
+            title_format: String::from("%A %d %B %Y"),
+            weekly_format: String::from("%G-W%V"),
+            monthly_format: String::from("%Y-%m"),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            notes_root: String::from("~/Notes/"),
+            editor: None,
+            extensions: vec![String::from("md")],
+            ignore: vec![],
+            timezone: String::from("utc"),
+            list_format: String::from("[{category}] {title} {tags} ({modified})"),
+            title_from: TitleSource::default_order(),
+            vaults: HashMap::new(),
+            search_mode: MatchMode::default(),
+            journal: JournalConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration, looking in order at the given path, the
+    /// `NOTEORG_CONFIG` environment variable and the default location.
+    /// A missing default file yields the default configuration.
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let explicit = path
+            .map(Path::to_path_buf)
+            .or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from));
+
+        let config = match explicit {
+            Some(path) => Self::from_file(&path)?,
+            None => {
+                let path = config_dir()?.join("config.toml");
+                if path.is_file() {
+                    Self::from_file(&path)?
+                } else {
+                    Self::default()
+                }
+            }
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse a configuration file, as YAML when its extension says so and
+    /// as TOML otherwise
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let content = read_to_string(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("Cannot read config {:?}: {}", path, e))
+        })?;
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext == "yaml" || ext == "yml")
+            .unwrap_or(false);
+
+        let parsed = if is_yaml {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid config {:?}: {}", path, e),
+            )
+        })
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        self.tz()?;
+        self.ignore_patterns()?;
+        // The journal category must stay inside the vault
+        note::category_dir(Path::new(""), &self.journal.category).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid journal category {:?}", self.journal.category),
+            )
+        })?;
+        for format in [
+            &self.journal.filename_format,
+            &self.journal.title_format,
+            &self.journal.weekly_format,
+            &self.journal.monthly_format,
+        ] {
+            if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid date format {:?} in journal config", format),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the notes root, e.g. from the `--root` flag
+    pub fn set_root(&mut self, root: &Path) {
+        self.notes_root = root.to_string_lossy().into_owned();
+    }
+
+    /// Notes root with `~` expanded
+    pub fn root(&self) -> io::Result<PathBuf> {
+        let root = self.notes_root.expand_home().map_err(io::Error::other)?;
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Notes root {:?} is not a directory", root),
+            ));
+        }
+        Ok(root)
+    }
+
+    /// Notes root with `~` expanded, created when it does not exist yet so
+    /// that commands adding notes can start a fresh vault
+    pub fn create_root(&self) -> io::Result<PathBuf> {
+        let root = self.notes_root.expand_home().map_err(io::Error::other)?;
+        create_dir_all(&root).map_err(|e| {
+            io::Error::new(e.kind(), format!("Cannot create notes root {:?}: {}", root, e))
+        })?;
+        Ok(root)
+    }
+
+    /// Settings of the vault at `root`, when the configuration has some
+    fn vault(&self, root: &Path) -> Option<&VaultConfig> {
+        let canonical = |path: PathBuf| path.canonicalize().unwrap_or(path);
+        let root = canonical(root.to_path_buf());
+        self.vaults.iter().find_map(|(path, vault)| {
+            let path = path.expand_home().ok()?;
+            (canonical(path) == root).then_some(vault)
+        })
+    }
+
+    /// Sources of the titles of the notes of the vault at `root`
+    pub fn title_from(&self, root: &Path) -> &[TitleSource] {
+        self.vault(root)
+            .and_then(|vault| vault.title_from.as_deref())
+            .unwrap_or(&self.title_from)
+    }
+
+    /// Timezone used to read and display note dates
+    pub fn tz(&self) -> io::Result<Timezone> {
+        parse_timezone(&self.timezone)
+    }
+
+    fn ignore_patterns(&self) -> io::Result<Vec<Pattern>> {
+        self.ignore
+            .iter()
+            .map(|glob| {
+                Pattern::new(glob).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid ignore glob {:?}: {}", glob, e),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn has_note_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| self.extensions.iter().any(|allowed| allowed == ext))
+            .unwrap_or(false)
+    }
+
+    /// All note files below `root`, filtered by extension and ignore globs
+    pub fn note_files(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let ignore = self.ignore_patterns()?;
+        let files = traversal::get_files(root)?
+            .into_iter()
+            .filter(|path| self.has_note_extension(path))
+            .filter(|path| {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                !ignore.iter().any(|pattern| pattern.matches_path(relative))
+            })
+            .collect();
+        Ok(files)
+    }
+}
+
+/// Directory holding the configuration file and templates
+pub fn config_dir() -> io::Result<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir).join("noteorg")),
+        _ => "~/.config/noteorg".expand_home().map_err(io::Error::other),
+    }
+}
+
+/// Directory holding caches such as the search index
+pub fn cache_dir() -> io::Result<PathBuf> {
+    match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir).join("noteorg")),
+        _ => "~/.cache/noteorg".expand_home().map_err(io::Error::other),
+    }
+}
+
+/// Directory holding user data such as the search history
+pub fn data_dir() -> io::Result<PathBuf> {
+    match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir).join("noteorg")),
+        _ => "~/.local/share/noteorg".expand_home().map_err(io::Error::other),
+    }
+}
+
+/// Timezone of the configuration: a fixed offset, or the local timezone
+/// whose offset depends on the date, daylight saving time included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timezone {
+    Fixed(FixedOffset),
+    Local,
+}
+
+impl TimeZone for Timezone {
+    type Offset = FixedOffset;
+
+    fn from_offset(offset: &FixedOffset) -> Self {
+        Timezone::Fixed(*offset)
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+        match self {
+            Timezone::Fixed(offset) => LocalResult::Single(*offset),
+            Timezone::Local => Local.offset_from_local_date(local),
+        }
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+        match self {
+            Timezone::Fixed(offset) => LocalResult::Single(*offset),
+            Timezone::Local => Local.offset_from_local_datetime(local),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+        match self {
+            Timezone::Fixed(offset) => *offset,
+            Timezone::Local => Local.offset_from_utc_date(utc),
+        }
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            Timezone::Fixed(offset) => *offset,
+            Timezone::Local => Local.offset_from_utc_datetime(utc),
+        }
+    }
+}
+
+impl std::fmt::Display for Timezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Timezone::Fixed(offset) => write!(f, "{}", offset),
+            Timezone::Local => write!(f, "local"),
+        }
+    }
+}
+
+fn parse_timezone(value: &str) -> io::Result<Timezone> {
+    match value.to_lowercase().as_str() {
+        "utc" | "z" => Ok(Timezone::Fixed(FixedOffset::east_opt(0).unwrap())),
+        "local" => Ok(Timezone::Local),
+        offset => offset.parse::<FixedOffset>().map(Timezone::Fixed).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Invalid timezone {:?}, expected \"utc\", \"local\" or an offset like \"+02:00\"",
+                    value
+                ),
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_titles_per_vault() {
+        let config: Config = toml::from_str(
+            r#"
+            title_from = ["heading", "filename"]
+
+            [vaults."/srv/work/"]
+            title_from = ["filename"]
+
+            [vaults."/srv/other"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.title_from(Path::new("/srv/work")), [TitleSource::Filename]);
+        assert_eq!(
+            config.title_from(Path::new("/srv/other")),
+            [TitleSource::Heading, TitleSource::Filename]
+        );
+        assert_eq!(config.title_from(Path::new("/srv")), config.title_from.as_slice());
+        assert!(toml::from_str::<Config>("[vaults.x]\ntitle = 1").is_err());
+    }
+
+    /// Configuration file with `content` in the temporary directory
+    fn config_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("noteorg-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_and_validates_files() {
+        let path = config_file("valid.toml", "timezone = \"+02:00\"\nextensions = [\"md\", \"txt\"]\n");
+        let config = Config::load(Some(&path)).unwrap();
+        assert_eq!(config.extensions, ["md", "txt"]);
+        assert_eq!(config.tz().unwrap().to_string(), "+02:00");
+        assert_eq!(config.journal.category, "journal");
+
+        let path = config_file("yaml.yml", "timezone: local\n");
+        assert_eq!(Config::load(Some(&path)).unwrap().tz().unwrap(), Timezone::Local);
+
+        for (name, content, message) in [
+            ("timezone.toml", "timezone = \"mars\"", "Invalid timezone \"mars\""),
+            ("unknown.toml", "colour = \"red\"", "unknown field `colour`"),
+            ("category.toml", "[journal]\ncategory = \"../out\"", "Invalid journal category"),
+            ("format.toml", "[journal]\ntitle_format = \"%Q\"", "Invalid date format"),
+            ("glob.toml", "ignore = [\"a[\"]", "Invalid ignore glob"),
+        ] {
+            let path = config_file(name, content);
+            let error = Config::load(Some(&path)).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", name);
+            assert!(error.to_string().contains(message), "{}: {}", name, error);
+        }
+
+        let missing = env::temp_dir().join("noteorg-missing-config.toml");
+        assert_eq!(Config::load(Some(&missing)).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn requires_an_existing_root() {
+        let mut config = Config::default();
+        let root = env::temp_dir().join(format!("noteorg-{}-root", std::process::id()));
+        config.set_root(&root);
+        assert_eq!(config.root().unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(config.create_root().unwrap(), root);
+        assert_eq!(config.root().unwrap(), root);
+        std::fs::remove_dir(&root).unwrap();
+    }
+
+    #[test]
+    fn resolves_local_offsets_per_date() {
+        let offset = |tz: Timezone, date: &str| {
+            let utc: NaiveDateTime = format!("{}T12:00:00", date).parse().unwrap();
+            tz.offset_from_utc_datetime(&utc)
+        };
+        // Both sides of a daylight saving time change, in any local timezone
+        for date in ["2026-01-15", "2026-07-15"] {
+            let local: NaiveDateTime = format!("{}T12:00:00", date).parse().unwrap();
+            assert_eq!(offset(Timezone::Local, date), Local.offset_from_utc_datetime(&local));
+        }
+        let fixed = parse_timezone("-05:30").unwrap();
+        assert_eq!(offset(fixed, "2026-01-15"), offset(fixed, "2026-07-15"));
+        assert_eq!(offset(fixed, "2026-01-15").local_minus_utc(), -(5 * 3600 + 1800));
+        assert_eq!(parse_timezone("UTC").unwrap().to_string(), "+00:00");
+    }
+}
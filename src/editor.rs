@@ -1,20 +1,260 @@
+use std::env;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-/// Launch neovim with the provided files
-pub fn launch_editor(files: &[PathBuf]) -> io::Result<()> {
-    if files.is_empty() {
+/// Editor used when neither the config nor the environment name one
+const DEFAULT_EDITOR: &str = "nvim";
+
+/// A file to open, optionally at a given (1-based) line
+#[derive(Debug, Clone)]
+pub struct EditTarget {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl From<PathBuf> for EditTarget {
+    fn from(path: PathBuf) -> Self {
+        Self { path, line: None }
+    }
+}
+
+/// Editor command split into its program and arguments.
+///
+/// Arguments may contain the `{file}` and `{line}` placeholders, e.g.
+/// `nvim +{line} {file}` or `hx {file}:{line}`. The arguments holding
+/// placeholders are repeated for every file; when no argument has a
+/// placeholder, the files are appended at the end of the command using the
+/// line syntax of well-known editors.
+#[derive(Debug, Clone)]
+pub struct EditorCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl EditorCommand {
+    /// Parse a shell-like command line such as `code --wait`
+    pub fn parse(command: &str) -> io::Result<Self> {
+        let mut words = shell_words::split(command).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid editor command {:?}: {}", command, e),
+            )
+        })?;
+        if words.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Editor command is empty",
+            ));
+        }
+        let program = words.remove(0);
+        Ok(Self {
+            program,
+            args: words,
+        })
+    }
+
+    /// Resolve the editor from the config, then `$VISUAL`, then `$EDITOR`,
+    /// falling back to neovim
+    pub fn resolve(configured: Option<&str>) -> io::Result<Self> {
+        let from_env = |name: &str| env::var(name).ok().filter(|value| !value.trim().is_empty());
+        let command = configured
+            .filter(|value| !value.trim().is_empty())
+            .map(String::from)
+            .or_else(|| from_env("VISUAL"))
+            .or_else(|| from_env("EDITOR"))
+            .unwrap_or_else(|| String::from(DEFAULT_EDITOR));
+        Self::parse(&command)
+    }
+
+    /// Lowercase name of the editor program, without directory or extension
+    fn name(&self) -> String {
+        Path::new(&self.program)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// Whether the editor applies every `+{line}` to the first file only,
+    /// as vi does, so that only the first file can be opened at a line
+    fn first_line_only(&self) -> bool {
+        matches!(self.name().as_str(), "vi" | "vim" | "nvim" | "gvim" | "mvim")
+    }
+
+    /// Per-file arguments used when the command has no placeholder
+    fn default_template(&self) -> Vec<String> {
+        let template: &[&str] = match self.name().as_str() {
+            "vi" | "vim" | "nvim" | "gvim" | "mvim" | "nano" | "emacs" | "emacsclient"
+            | "kak" | "micro" | "joe" | "ne" => &["+{line}", "{file}"],
+            "hx" | "helix" | "subl" | "zed" => &["{file}:{line}"],
+            "code" | "code-insiders" | "codium" | "vscodium" | "cursor" => &["-g", "{file}:{line}"],
+            _ => &["{file}"],
+        };
+        template.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn build(&self, targets: &[EditTarget]) -> Command {
+        let has_placeholder = |arg: &String| arg.contains("{file}") || arg.contains("{line}");
+        let first = self.args.iter().position(has_placeholder);
+        let last = self.args.iter().rposition(has_placeholder);
+
+        // Files after the first are opened without a line when the editor
+        // would move the cursor of the first file instead
+        let targets: Vec<EditTarget> = targets
+            .iter()
+            .enumerate()
+            .map(|(i, target)| EditTarget {
+                path: target.path.clone(),
+                line: target.line.filter(|_| i == 0 || !self.first_line_only()),
+            })
+            .collect();
+
+        let mut cmd = Command::new(&self.program);
+        match (first, last) {
+            (Some(first), Some(last)) => {
+                // A template naming the line but not the file, such as
+                // `nvim +{line}`, gets the file after the line
+                let mut template = self.args[first..=last].to_vec();
+                if !template.iter().any(|arg| arg.contains("{file}")) {
+                    template.push(String::from("{file}"));
+                }
+                cmd.args(&self.args[..first]);
+                for target in &targets {
+                    cmd.args(expand(&template, target));
+                }
+                cmd.args(&self.args[last + 1..]);
+            }
+            _ => {
+                let template = self.default_template();
+                cmd.args(&self.args);
+                for target in &targets {
+                    cmd.args(expand(&template, target));
+                }
+            }
+        }
+        cmd
+    }
+}
+
+/// Argument `arg` without its line part, for a target without a line: the
+/// file is kept without what follows it up to the line (`{file}:{line}:1`
+/// and `{file}#L{line}` give `{file}`), and an argument needing a line but
+/// no file is dropped
+fn without_line(arg: &str) -> Option<String> {
+    let (Some(line), Some(file)) = (arg.find("{line}"), arg.find("{file}")) else {
+        return (!arg.contains("{line}")).then(|| arg.to_string());
+    };
+    let file_end = file + "{file}".len();
+    let kept = if line >= file_end {
+        &arg[..file_end]
+    } else {
+        &arg[file..]
+    };
+    without_line(kept)
+}
+
+/// Fill the placeholders of a per-file argument group, leaving out the line
+/// parts when the target has no line
+fn expand(template: &[String], target: &EditTarget) -> Vec<String> {
+    let file = target.path.to_string_lossy();
+    template
+        .iter()
+        .filter_map(|arg| match target.line {
+            Some(line) => Some(arg.replace("{line}", &line.to_string())),
+            None => without_line(arg),
+        })
+        .map(|arg| arg.replace("{file}", &file))
+        .collect()
+}
+
+/// Launch the configured editor with the provided files, each at its line
+/// when known, and wait for it
+pub fn launch_editor(targets: &[EditTarget], editor: Option<&str>) -> io::Result<()> {
+    if targets.is_empty() {
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
             "No files provided to editor",
         ));
     }
 
-    let mut cmd = Command::new("nvim");
-    for file in files {
-        cmd.arg(file);
+    let editor = EditorCommand::resolve(editor)?;
+    let status = editor.build(targets).status().map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Cannot launch editor {:?}: {}", editor.program, e),
+        )
+    })?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "Editor {:?} exited with {}",
+            editor.program, status
+        )));
     }
-    cmd.status()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(editor: &str, targets: &[(&str, Option<usize>)]) -> Vec<String> {
+        let targets: Vec<EditTarget> = targets
+            .iter()
+            .map(|(path, line)| EditTarget {
+                path: PathBuf::from(path),
+                line: *line,
+            })
+            .collect();
+        EditorCommand::parse(editor)
+            .unwrap()
+            .build(&targets)
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn vi_opens_only_the_first_file_at_its_line() {
+        assert_eq!(
+            args("nvim", &[("a.md", Some(3)), ("b.md", Some(7))]),
+            ["+3", "a.md", "b.md"]
+        );
+        assert_eq!(
+            args("nvim +{line} {file}", &[("a.md", Some(3)), ("b.md", Some(7))]),
+            ["+3", "a.md", "b.md"]
+        );
+    }
+
+    #[test]
+    fn other_editors_open_every_file_at_its_line() {
+        assert_eq!(
+            args("hx", &[("a.md", Some(3)), ("b.md", None)]),
+            ["a.md:3", "b.md"]
+        );
+        assert_eq!(
+            args("emacs", &[("a.md", Some(3)), ("b.md", Some(7))]),
+            ["+3", "a.md", "+7", "b.md"]
+        );
+    }
+
+    #[test]
+    fn template_without_file_gets_the_file_appended() {
+        assert_eq!(args("nvim +{line}", &[("a.md", Some(3))]), ["+3", "a.md"]);
+        assert_eq!(
+            args("code --wait -g {file}:{line} --new-window", &[("a.md", None)]),
+            ["--wait", "-g", "a.md", "--new-window"]
+        );
+    }
+
+    #[test]
+    fn keeps_the_file_of_arguments_with_a_line() {
+        let targets = [("a.md", Some(3)), ("b.md", None)];
+        assert_eq!(args("browse {file}#L{line}", &targets), ["a.md#L3", "b.md"]);
+        assert_eq!(args("ed --at={line}@{file}", &targets), ["--at=3@a.md", "b.md"]);
+        assert_eq!(
+            args("ed -l {line} {file}:{line}:1", &targets),
+            ["-l", "3", "a.md:3:1", "b.md"]
+        );
+    }
+}
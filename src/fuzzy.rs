@@ -0,0 +1,199 @@
+// Fuzzy and literal matching for the interactive search
+//
+// The fuzzy scorer is a Smith-Waterman style local alignment, in the spirit
+// of fzf: every pattern character must appear in order in the text, matches
+// at word boundaries and consecutive matches earn bonuses and gaps between
+// matched characters cost a penalty. Matching is case-insensitive unless the
+// pattern contains an uppercase letter.
+
+const SCORE_MATCH: i32 = 16;
+const GAP_START: i32 = -3;
+const GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL_CASE: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+/// The bonus of the first pattern character counts more
+const FIRST_CHAR_MULTIPLIER: i32 = 2;
+const UNMATCHED: i32 = i32::MIN / 2;
+
+/// Score of a match and the positions (in characters) of the matched text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+fn is_case_sensitive(pattern: &str) -> bool {
+    pattern.chars().any(char::is_uppercase)
+}
+
+fn normalize(c: char, case_sensitive: bool) -> char {
+    if case_sensitive {
+        c
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+/// Bonus for matching the character at `index`, depending on the previous one
+fn bonus(text: &[char], index: usize) -> i32 {
+    let current = text[index];
+    let Some(previous) = index.checked_sub(1).map(|i| text[i]) else {
+        return BONUS_BOUNDARY;
+    };
+    if current.is_alphanumeric() && !previous.is_alphanumeric() {
+        BONUS_BOUNDARY
+    } else if current.is_uppercase() && previous.is_lowercase()
+        || current.is_numeric() && !previous.is_numeric()
+    {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// Score of reaching a match at `to` from a match of the previous pattern
+/// character at `from`
+fn transition(from: usize, to: usize) -> i32 {
+    if from + 1 == to {
+        BONUS_CONSECUTIVE
+    } else {
+        GAP_START + GAP_EXTENSION * (to - from - 2) as i32
+    }
+}
+
+/// Best fuzzy alignment of `pattern` in `text`, `None` unless every pattern
+/// character appears in order
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<TextMatch> {
+    let case_sensitive = is_case_sensitive(pattern);
+    let pattern: Vec<char> = pattern.chars().map(|c| normalize(c, case_sensitive)).collect();
+    if pattern.is_empty() {
+        return None;
+    }
+    let original: Vec<char> = text.chars().collect();
+    let text: Vec<char> = original.iter().map(|c| normalize(*c, case_sensitive)).collect();
+
+    // Cheap subsequence check before filling the score matrix
+    let mut remaining = pattern.iter().peekable();
+    for c in &text {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    if remaining.peek().is_some() {
+        return None;
+    }
+
+    let bonuses: Vec<i32> = (0..original.len()).map(|i| bonus(&original, i)).collect();
+
+    // scores[i][j]: best alignment of pattern[..=i] with pattern[i] at text[j]
+    let mut scores: Vec<Vec<i32>> = Vec::with_capacity(pattern.len());
+    for (i, p) in pattern.iter().enumerate() {
+        let mut row = vec![UNMATCHED; text.len()];
+        let mut gap_best = UNMATCHED;
+        for j in 0..text.len() {
+            let best_previous = if i == 0 {
+                0
+            } else {
+                // Best previous match at j - 2 or before, with the gap penalty
+                if j >= 2 {
+                    gap_best = (gap_best + GAP_EXTENSION).max(scores[i - 1][j - 2] + GAP_START);
+                }
+                let consecutive = if j >= 1 {
+                    scores[i - 1][j - 1] + BONUS_CONSECUTIVE
+                } else {
+                    UNMATCHED
+                };
+                gap_best.max(consecutive)
+            };
+            if text[j] == *p && best_previous > UNMATCHED / 2 {
+                let multiplier = if i == 0 { FIRST_CHAR_MULTIPLIER } else { 1 };
+                row[j] = best_previous + SCORE_MATCH + bonuses[j] * multiplier;
+            }
+        }
+        scores.push(row);
+    }
+
+    let last = scores.last()?;
+    let (mut j, &score) = last
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))?;
+    if score <= UNMATCHED / 2 {
+        return None;
+    }
+
+    // Walk back through the matrix to recover the matched positions
+    let mut positions = vec![j];
+    for i in (1..pattern.len()).rev() {
+        let target = scores[i][j] - SCORE_MATCH - bonuses[j];
+        j = (0..j)
+            .rev()
+            .find(|&k| {
+                scores[i - 1][k] > UNMATCHED / 2 && scores[i - 1][k] + transition(k, j) == target
+            })?;
+        positions.push(j);
+    }
+    positions.reverse();
+    Some(TextMatch { score, positions })
+}
+
+/// First occurrence of `pattern` in `text`, with the same case rules as
+/// `fuzzy_match`
+pub fn literal_match(pattern: &str, text: &str) -> Option<TextMatch> {
+    let case_sensitive = is_case_sensitive(pattern);
+    let pattern: Vec<char> = pattern.chars().map(|c| normalize(c, case_sensitive)).collect();
+    let text: Vec<char> = text.chars().map(|c| normalize(c, case_sensitive)).collect();
+    if pattern.is_empty() || pattern.len() > text.len() {
+        return None;
+    }
+    let start = text.windows(pattern.len()).position(|window| window == pattern)?;
+    Some(TextMatch {
+        score: SCORE_MATCH * pattern.len() as i32,
+        positions: (start..start + pattern.len()).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(pattern: &str, text: &str) -> Option<Vec<usize>> {
+        fuzzy_match(pattern, text).map(|found| found.positions)
+    }
+
+    #[test]
+    fn requires_every_character_in_order() {
+        assert_eq!(positions("abc", "acb"), None);
+        assert_eq!(positions("abc", "ab"), None);
+        assert_eq!(positions("", "abc"), None);
+        assert_eq!(positions("ac", "abc"), Some(vec![0, 2]));
+    }
+
+    #[test]
+    fn ignores_case_unless_the_pattern_has_uppercase() {
+        assert!(fuzzy_match("note", "My NOTES").is_some());
+        assert!(fuzzy_match("Note", "my notes").is_none());
+        assert!(fuzzy_match("Note", "My Notes").is_some());
+        assert!(literal_match("note", "NOTE").is_some());
+        assert!(literal_match("Note", "note").is_none());
+    }
+
+    #[test]
+    fn ranks_boundaries_and_consecutive_matches_higher() {
+        let mut candidates = ["xfxb", "foo bar", "xfb", "fb"];
+        candidates.sort_by_key(|text| -fuzzy_match("fb", text).unwrap().score);
+        assert_eq!(candidates, ["fb", "foo bar", "xfb", "xfxb"]);
+    }
+
+    #[test]
+    fn returns_the_positions_of_the_best_alignment() {
+        // Consecutive characters after a boundary beat the scattered ones
+        assert_eq!(positions("abc", "axbxc abc"), Some(vec![6, 7, 8]));
+        assert_eq!(positions("fb", "foo bar"), Some(vec![0, 4]));
+        assert_eq!(positions("nb", "fooNoteBar"), Some(vec![3, 7]));
+        // Positions count characters, not bytes
+        assert_eq!(positions("ab", "éa b"), Some(vec![1, 3]));
+        assert_eq!(literal_match("ot", "nöte note").unwrap().positions, [6, 7]);
+    }
+}
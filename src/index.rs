@@ -0,0 +1,269 @@
+// Persistent note index
+//
+// Parsed notes are cached as JSON under `~/.cache/noteorg/`, one file per
+// notes root. Entries are keyed by path and reused as long as the file
+// modification time and size are unchanged, so only new or edited notes are
+// read again.
+
+use crate::bm25::{DocumentTerms, Ranker};
+use crate::config::{self, Config};
+use crate::note::{self, Note, TitleSource};
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bumped whenever the cached format changes, invalidating older caches
+const INDEX_VERSION: u32 = 6;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    modified: SystemTime,
+    size: u64,
+    note: Note<Utc>,
+    /// Tokenized fields, so that ranking does not re-tokenize every note
+    terms: DocumentTerms,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct IndexData {
+    version: u32,
+    root: PathBuf,
+    /// Timezone frontmatter dates without an offset were read in
+    timezone: String,
+    /// Sources the titles were resolved from
+    title_from: Vec<TitleSource>,
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+pub struct Index {
+    root: PathBuf,
+    cache_path: PathBuf,
+    data: IndexData,
+}
+
+/// `root` without symlinks, `.` or `..`, so that different spellings of the
+/// same root share one cache
+fn canonical(root: &Path) -> PathBuf {
+    root.canonicalize().unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Cache file of a notes root, named after its path
+fn cache_file(root: &Path) -> io::Result<PathBuf> {
+    let name: String = root
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    Ok(config::cache_dir()?.join(format!("index-{}.json", name.trim_matches('_'))))
+}
+
+/// Cached index of `root`, unless it was built with other settings
+fn load(cache_path: &Path, root: &Path, timezone: &str, config: &Config) -> Option<IndexData> {
+    let file = File::open(cache_path).ok()?;
+    let data: IndexData = serde_json::from_reader(BufReader::new(file)).ok()?;
+    (data.version == INDEX_VERSION
+        && data.root == canonical(root)
+        && data.timezone == timezone
+        && data.title_from == config.title_from(root))
+        .then_some(data)
+}
+
+impl Index {
+    /// Load the cached index of `root` and bring it up to date with the
+    /// files on disk
+    pub fn open(root: &Path, config: &Config) -> io::Result<Self> {
+        Self::open_at(root, cache_file(&canonical(root))?, config)
+    }
+
+    fn open_at(root: &Path, cache_path: PathBuf, config: &Config) -> io::Result<Self> {
+        let timezone = config.tz()?.to_string();
+        let data = load(&cache_path, root, &timezone, config).unwrap_or_else(|| IndexData {
+            version: INDEX_VERSION,
+            root: canonical(root),
+            timezone,
+            title_from: config.title_from(root).to_vec(),
+            entries: BTreeMap::new(),
+        });
+        let mut index = Self {
+            root: root.to_path_buf(),
+            cache_path,
+            data,
+        };
+        index.refresh(config)?;
+        Ok(index)
+    }
+
+    /// Re-read notes whose modification time or size changed and drop
+    /// deleted ones, saving the cache when anything changed. Returns whether
+    /// anything changed.
+    pub fn refresh(&mut self, config: &Config) -> io::Result<bool> {
+        let files = config.note_files(&self.root)?;
+        let tz = config.tz()?;
+        let title_from = config.title_from(&self.root);
+        let mut changed = false;
+        let mut entries = BTreeMap::new();
+
+        for path in files {
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let Ok(modified) = metadata.modified() else {
+                continue;
+            };
+            let size = metadata.len();
+
+            match self.data.entries.remove(&path) {
+                Some(entry) if entry.modified == modified && entry.size == size => {
+                    entries.insert(path, entry);
+                }
+                _ => {
+                    changed = true;
+                    // Unreadable notes are skipped, as when reading directly
+                    if let Ok(note) = note::read_note(&path, &self.root, &tz, title_from)
+                        .map(|note| note.with_timezone(&Utc))
+                    {
+                        let terms = DocumentTerms::from_note(&note);
+                        entries.insert(
+                            path,
+                            Entry {
+                                modified,
+                                size,
+                                note,
+                                terms,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        // Whatever is left was deleted or is now ignored
+        changed |= !self.data.entries.is_empty();
+        self.data.entries = entries;
+        if changed {
+            // The index is only a cache: failing to write it must not make
+            // the command fail
+            let _ = self.save();
+        }
+        Ok(changed)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Unique to this process, so that concurrent runs do not write to
+        // the same temporary file
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.subsec_nanos());
+        let tmp_path = self
+            .cache_path
+            .with_extension(format!("json.{}-{}.tmp", std::process::id(), nanos));
+        let written = File::create(&tmp_path).and_then(|file| {
+            serde_json::to_writer(BufWriter::new(file), &self.data).map_err(io::Error::other)
+        });
+        let result = written.and_then(|()| fs::rename(&tmp_path, &self.cache_path));
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// All indexed notes, in path order, with dates in `tz`
+    pub fn notes<Tz: TimeZone>(&self, tz: &Tz) -> Vec<Note<Tz>> {
+        self.data
+            .entries
+            .values()
+            .map(|entry| entry.note.with_timezone(tz))
+            .collect()
+    }
+
+    /// BM25 ranker whose document ids are positions in `notes()`
+    pub fn ranker(&self) -> Ranker {
+        Ranker::new(self.data.entries.values().map(|entry| &entry.terms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn titles(index: &Index) -> Vec<String> {
+        index
+            .notes(&Utc)
+            .into_iter()
+            .map(|note| note.metadata.title)
+            .collect()
+    }
+
+    #[test]
+    fn refreshes_changed_deleted_and_ignored_notes() {
+        let root = env::temp_dir().join(format!("noteorg-{}-index", std::process::id()));
+        let cache_path = root.with_extension("json");
+        fs::create_dir_all(root.join("drafts")).unwrap();
+        fs::write(root.join("a.md"), "# Alpha").unwrap();
+        fs::write(root.join("b.md"), "# Beta").unwrap();
+        fs::write(root.join("c.txt"), "# Text").unwrap();
+        fs::write(root.join("drafts/d.md"), "# Draft").unwrap();
+        let mut config = Config {
+            ignore: vec![String::from("drafts/**")],
+            ..Config::default()
+        };
+
+        let mut index = Index::open_at(&root, cache_path.clone(), &config).unwrap();
+        assert_eq!(titles(&index), ["Alpha", "Beta"]);
+        assert!(!index.refresh(&config).unwrap());
+
+        // The size changes too, in case the modification time does not
+        fs::write(root.join("a.md"), "# Alpha, edited").unwrap();
+        assert!(index.refresh(&config).unwrap());
+        assert_eq!(titles(&index), ["Alpha, edited", "Beta"]);
+
+        fs::remove_file(root.join("b.md")).unwrap();
+        assert!(index.refresh(&config).unwrap());
+        assert_eq!(titles(&index), ["Alpha, edited"]);
+
+        config.ignore.push(String::from("a.md"));
+        assert!(index.refresh(&config).unwrap());
+        assert!(titles(&index).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_file(&cache_path).unwrap();
+    }
+
+    #[test]
+    fn invalidates_the_cache_when_settings_change() {
+        let root = env::temp_dir().join(format!("noteorg-{}-cache", std::process::id()));
+        let cache_path = root.with_extension("json");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.md"), "# Alpha").unwrap();
+        let config = Config::default();
+        let timezone = config.tz().unwrap().to_string();
+
+        let index = Index::open_at(&root, cache_path.clone(), &config).unwrap();
+        let cached = load(&cache_path, &root, &timezone, &config).unwrap();
+        assert_eq!(cached.entries.len(), 1);
+        // Another spelling of the same root
+        assert!(load(&cache_path, &root.join("."), &timezone, &config).is_some());
+        assert_eq!(
+            titles(&Index::open_at(&root, cache_path.clone(), &config).unwrap()),
+            titles(&index)
+        );
+
+        assert!(load(&cache_path, &root, "+02:00", &config).is_none());
+        assert!(load(&cache_path, &env::temp_dir(), &timezone, &config).is_none());
+        let config = Config {
+            title_from: vec![TitleSource::Filename],
+            ..Config::default()
+        };
+        assert!(load(&cache_path, &root, &timezone, &config).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_file(&cache_path).unwrap();
+    }
+}
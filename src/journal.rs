@@ -0,0 +1,291 @@
+// Daily journal notes and their weekly/monthly rollups
+
+use crate::config::Config;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use regex::Regex;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// Heading of the list of daily notes in rollups
+const DAILY_NOTES_HEADING: &str = "## Daily notes";
+
+/// Offsets such as `-3d` or `+2w`
+static OFFSET: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^([+-])(\d+)([dwmy])$").unwrap());
+
+/// `3 days ago` or `in 2 weeks`
+static RELATIVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(\d+) (day|week|month|year)s? ago|in (\d+) (day|week|month|year)s?)$").unwrap()
+});
+
+/// Span of time covered by a journal note
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+fn date_error(input: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Cannot understand date {:?}, try \"today\", \"2026-10-01\", \"-3d\", \"last friday\" or \"2 weeks ago\"",
+            input
+        ),
+    )
+}
+
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    match name {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Move `date` by `amount` units (`d`, `w`, `m` or `y`), backwards when
+/// `backwards` is set
+fn shift(date: NaiveDate, amount: u32, unit: &str, backwards: bool) -> Option<NaiveDate> {
+    match (unit.chars().next()?, backwards) {
+        ('d', false) => date.checked_add_days(Days::new(amount.into())),
+        ('d', true) => date.checked_sub_days(Days::new(amount.into())),
+        ('w', false) => date.checked_add_days(Days::new(u64::from(amount) * 7)),
+        ('w', true) => date.checked_sub_days(Days::new(u64::from(amount) * 7)),
+        ('m', false) => date.checked_add_months(Months::new(amount)),
+        ('m', true) => date.checked_sub_months(Months::new(amount)),
+        ('y', false) => date.checked_add_months(Months::new(amount.checked_mul(12)?)),
+        ('y', true) => date.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// Parse a date relative to `today`. Accepted forms are `today`,
+/// `yesterday`, `tomorrow`, ISO dates (`2026-10-01`), offsets (`-3d`, `+2w`,
+/// `-1m`, `-1y`), `3 days ago`, `in 2 weeks`, and weekdays (`friday` is the
+/// latest one up to today, `last friday`, `next friday`, `this friday`).
+pub fn parse_date(input: &str, today: NaiveDate) -> io::Result<NaiveDate> {
+    let normalized = input.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+
+    match normalized.as_str() {
+        "" | "today" | "now" => return Ok(today),
+        "yesterday" => return today.pred_opt().ok_or_else(|| date_error(input)),
+        "tomorrow" => return today.succ_opt().ok_or_else(|| date_error(input)),
+        _ => {}
+    }
+
+    for format in ["%Y-%m-%d", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(&normalized, format) {
+            return Ok(date);
+        }
+    }
+
+    if let Some(caps) = OFFSET.captures(&normalized) {
+        let amount = caps[2].parse().map_err(|_| date_error(input))?;
+        return shift(today, amount, &caps[3], &caps[1] == "-").ok_or_else(|| date_error(input));
+    }
+
+    if let Some(caps) = RELATIVE.captures(&normalized) {
+        let (amount, unit, backwards) = match (caps.get(1), caps.get(3)) {
+            (Some(amount), _) => (amount.as_str(), &caps[2], true),
+            (None, Some(amount)) => (amount.as_str(), &caps[4], false),
+            _ => return Err(date_error(input)),
+        };
+        let amount = amount.parse().map_err(|_| date_error(input))?;
+        return shift(today, amount, unit, backwards).ok_or_else(|| date_error(input));
+    }
+
+    let (qualifier, name) = match normalized.split_once(' ') {
+        Some((qualifier, name)) => (Some(qualifier), name),
+        None => (None, normalized.as_str()),
+    };
+    let weekday = parse_weekday(name).ok_or_else(|| date_error(input))?;
+    let days_back = (7 + today.weekday().num_days_from_monday()
+        - weekday.num_days_from_monday())
+        % 7;
+    let days_forward = (7 - days_back) % 7;
+    let date = match qualifier {
+        None => today.checked_sub_days(Days::new(days_back.into())),
+        Some("last") => today.checked_sub_days(Days::new(if days_back == 0 { 7 } else { days_back }.into())),
+        Some("next") => today.checked_add_days(Days::new(if days_forward == 0 { 7 } else { days_forward }.into())),
+        Some("this") => today
+            .week(Weekday::Mon)
+            .first_day()
+            .checked_add_days(Days::new(weekday.num_days_from_monday().into())),
+        Some(_) => None,
+    };
+    date.ok_or_else(|| date_error(input))
+}
+
+/// First and last day of the period containing `date`
+pub fn period_bounds(date: NaiveDate, period: Period) -> (NaiveDate, NaiveDate) {
+    match period {
+        Period::Day => (date, date),
+        Period::Week => {
+            let week = date.week(Weekday::Mon);
+            (week.first_day(), week.last_day())
+        }
+        Period::Month => {
+            let first = date.with_day(1).unwrap_or(date);
+            let last = first
+                .checked_add_months(Months::new(1))
+                .and_then(|next| next.pred_opt())
+                .unwrap_or(date);
+            (first, last)
+        }
+    }
+}
+
+/// File name (without extension) of the journal note of a period
+pub fn note_stem(date: NaiveDate, period: Period, config: &Config) -> String {
+    let format = match period {
+        Period::Day => &config.journal.filename_format,
+        Period::Week => &config.journal.weekly_format,
+        Period::Month => &config.journal.monthly_format,
+    };
+    date.format(format).to_string()
+}
+
+/// Title of the journal note of a period
+pub fn note_title(date: NaiveDate, period: Period, config: &Config) -> String {
+    match period {
+        Period::Day => date.format(&config.journal.title_format).to_string(),
+        Period::Week => format!("Week {}", date.format("%V, %G")),
+        Period::Month => date.format("%B %Y").to_string(),
+    }
+}
+
+/// Path of the journal note of a period
+pub fn note_path(root: &Path, date: NaiveDate, period: Period, config: &Config) -> PathBuf {
+    let extension = config.extensions.first().map_or("md", String::as_str);
+    root.join(&config.journal.category)
+        .join(format!("{}.{}", note_stem(date, period, config), extension))
+}
+
+/// Wiki-links to the existing daily notes of the period containing `date`
+/// that `content` does not link to yet
+pub fn missing_daily_links(
+    root: &Path,
+    date: NaiveDate,
+    period: Period,
+    config: &Config,
+    content: &str,
+) -> Vec<String> {
+    let (first, last) = period_bounds(date, period);
+    first
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .filter(|day| note_path(root, *day, Period::Day, config).is_file())
+        .map(|day| format!("[[{}]]", note_stem(day, Period::Day, config)))
+        .filter(|link| !content.contains(link.as_str()))
+        .collect()
+}
+
+/// Body of a new rollup listing `links`
+pub fn rollup_body(links: &[String]) -> String {
+    let mut body = format!("{}\n\n", DAILY_NOTES_HEADING);
+    for link in links {
+        body.push_str(&format!("- {}\n", link));
+    }
+    body
+}
+
+/// Rollup `content` with `links` added at the end of its daily notes
+/// section, which is appended when missing
+pub fn add_daily_links(content: &str, links: &[String]) -> String {
+    let items: String = links.iter().map(|link| format!("- {}\n", link)).collect();
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let Some(heading) = lines
+        .iter()
+        .position(|line| line.trim_end() == DAILY_NOTES_HEADING)
+    else {
+        let mut content = content.to_string();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        return content + &rollup_body(links);
+    };
+
+    // The section ends at the next heading, the links go after its last
+    // non-blank line
+    let end = lines[heading + 1..]
+        .iter()
+        .position(|line| line.starts_with('#'))
+        .map_or(lines.len(), |position| heading + 1 + position);
+    let last = (heading..end)
+        .rev()
+        .find(|&i| !lines[i].trim().is_empty())
+        .unwrap_or(heading);
+    let mut updated: String = lines[..=last].concat();
+    if !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    if last == heading {
+        updated.push('\n');
+    }
+    updated.push_str(&items);
+    updated.push_str(&lines[last + 1..].concat());
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parses_relative_dates() {
+        // A Saturday
+        let today = date("2026-10-17");
+        for (input, expected) in [
+            ("today", "2026-10-17"),
+            ("Yesterday", "2026-10-16"),
+            ("2026-01-02", "2026-01-02"),
+            ("2026/01/02", "2026-01-02"),
+            ("-3d", "2026-10-14"),
+            ("+2w", "2026-10-31"),
+            ("-1m", "2026-09-17"),
+            ("3 days ago", "2026-10-14"),
+            ("in 1 year", "2027-10-17"),
+            ("friday", "2026-10-16"),
+            ("saturday", "2026-10-17"),
+            ("last saturday", "2026-10-10"),
+            ("next sat", "2026-10-24"),
+            ("this monday", "2026-10-12"),
+        ] {
+            assert_eq!(parse_date(input, today).unwrap(), date(expected), "{}", input);
+        }
+        for input in ["someday", "3 fortnights ago", "-3x", "last"] {
+            assert!(parse_date(input, today).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn adds_daily_links_under_their_heading() {
+        let links = [String::from("[[2026-10-13]]")];
+        let content = "# Week\n\n## Daily notes\n\n- [[2026-10-12]]\n\n## Review\n\nGood week.\n";
+        assert_eq!(
+            add_daily_links(content, &links),
+            "# Week\n\n## Daily notes\n\n- [[2026-10-12]]\n- [[2026-10-13]]\n\n## Review\n\nGood week.\n"
+        );
+        assert_eq!(
+            add_daily_links("## Daily notes\n", &links),
+            "## Daily notes\n\n- [[2026-10-13]]\n"
+        );
+        assert_eq!(
+            add_daily_links("Notes", &links),
+            "Notes\n\n## Daily notes\n\n- [[2026-10-13]]\n"
+        );
+    }
+}
@@ -1,5 +1,17 @@
+pub mod bm25;
 pub mod cli;
+pub mod config;
 pub mod editor;
+pub mod fuzzy;
+pub mod index;
+pub mod journal;
+pub mod line_editor;
+pub mod links;
 pub mod note;
+pub mod prompt;
+pub mod query;
 pub mod search;
+pub mod stats;
+pub mod tags;
+pub mod template;
 pub mod traversal;
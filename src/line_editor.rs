@@ -0,0 +1,299 @@
+// Single line text editing and input history for the interactive search
+//
+// The cursor moves over grapheme clusters, so that accented letters written
+// with combining marks or emoji sequences are edited as one character.
+
+use crate::config;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// File of the search history in the data directory
+const HISTORY_FILE: &str = "search_history";
+
+/// Number of entries kept in the history
+const HISTORY_SIZE: usize = 500;
+
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    text: String,
+    /// Byte offset of the cursor, always on a grapheme boundary
+    cursor: usize,
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, moving the cursor to its end
+    pub fn set_text(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    /// Whether the cursor is before the first character
+    pub fn at_start(&self) -> bool {
+        self.cursor == 0
+    }
+
+    /// Whether the cursor is after the last character
+    pub fn at_end(&self) -> bool {
+        self.cursor == self.text.len()
+    }
+
+    /// Terminal columns taken by the text before the cursor
+    pub fn cursor_column(&self) -> usize {
+        self.text[..self.cursor].width()
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map_or(self.cursor, |grapheme| self.cursor + grapheme.len())
+    }
+
+    /// Start of the word before the cursor, words being separated by
+    /// characters for which `is_separator` holds
+    fn word_start(&self, is_separator: impl Fn(char) -> bool) -> usize {
+        let mut start = self.cursor;
+        let mut in_word = false;
+        for (i, grapheme) in self.text[..self.cursor].grapheme_indices(true).rev() {
+            let separator = grapheme.chars().next().is_some_and(&is_separator);
+            if separator && in_word {
+                break;
+            }
+            in_word |= !separator;
+            start = i;
+        }
+        start
+    }
+
+    fn delete_back_to(&mut self, start: usize) {
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Apply an editing key, returning whether it was one
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if control => self.cursor = 0,
+            KeyCode::Char('e') if control => self.cursor = self.text.len(),
+            // Delete the previous whitespace separated word
+            KeyCode::Char('w') if control => self.delete_back_to(self.word_start(char::is_whitespace)),
+            KeyCode::Char('u') if control => self.delete_back_to(0),
+            KeyCode::Char(c) if !control && !alt => {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            // Delete the previous alphanumeric word
+            KeyCode::Backspace if alt => {
+                self.delete_back_to(self.word_start(|c| !c.is_alphanumeric()))
+            }
+            KeyCode::Backspace => self.delete_back_to(self.previous_boundary()),
+            KeyCode::Delete => {
+                let end = self.next_boundary();
+                self.text.replace_range(self.cursor..end, "");
+            }
+            KeyCode::Left => self.cursor = self.previous_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Previous inputs, oldest first, persisted in the data directory
+#[derive(Debug, Default)]
+pub struct History {
+    path: Option<PathBuf>,
+    entries: Vec<String>,
+    /// Entry being shown while browsing
+    position: Option<usize>,
+    /// Input typed before browsing, restored after the newest entry
+    draft: String,
+}
+
+impl History {
+    /// Read the search history, starting empty when it cannot be read
+    pub fn load() -> Self {
+        let path = config::data_dir().ok().map(|dir| dir.join(HISTORY_FILE));
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| content.lines().map(String::from).collect())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries,
+            position: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Entry before the one shown, `current` being kept as the draft when
+    /// browsing starts
+    pub fn older(&mut self, current: &str) -> Option<String> {
+        let position = match self.position {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(position) => position.checked_sub(1)?,
+        };
+        self.position = Some(position);
+        Some(self.entries[position].clone())
+    }
+
+    /// Entry after the one shown, or the draft after the newest one
+    pub fn newer(&mut self) -> Option<String> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(self.entries[position + 1].clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    /// Stop browsing, e.g. once the shown entry is edited
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// Record an input as the newest entry and save the history
+    pub fn add(&mut self, entry: &str) -> io::Result<()> {
+        self.position = None;
+        let entry = entry.trim();
+        if entry.is_empty() || entry.contains('\n') {
+            return Ok(());
+        }
+        self.entries.retain(|existing| existing != entry);
+        self.entries.push(entry.to_string());
+        let excess = self.entries.len().saturating_sub(HISTORY_SIZE);
+        self.entries.drain(..excess);
+
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, self.entries.join("\n") + "\n")?;
+        fs::rename(&temporary, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        editor.set_text(text.to_string());
+        editor
+    }
+
+    fn press(editor: &mut LineEditor, code: KeyCode, modifiers: KeyModifiers) {
+        assert!(editor.handle_key(KeyEvent::new(code, modifiers)));
+    }
+
+    #[test]
+    fn edits_grapheme_clusters() {
+        // `e` with a combining acute accent, and a family emoji sequence
+        let mut line = editor("ae\u{301}👨‍👩‍👧b");
+        press(&mut line, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut line, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(line.text(), "ae\u{301}b");
+        press(&mut line, KeyCode::Left, KeyModifiers::NONE);
+        assert_eq!(line.cursor_column(), 1);
+        press(&mut line, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(line.text(), "ab");
+        press(&mut line, KeyCode::Char('é'), KeyModifiers::NONE);
+        press(&mut line, KeyCode::Right, KeyModifiers::NONE);
+        assert_eq!(line.text(), "aéb");
+        assert!(line.at_end());
+        // Nothing to delete after the end
+        press(&mut line, KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(line.text(), "aéb");
+    }
+
+    #[test]
+    fn deletes_words() {
+        let mut line = editor("tag:rust  foo-bar ");
+        press(&mut line, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(line.text(), "tag:rust  foo-");
+        press(&mut line, KeyCode::Backspace, KeyModifiers::ALT);
+        assert_eq!(line.text(), "tag:rust  ");
+        press(&mut line, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(line.text(), "");
+
+        let mut line = editor("one two-three");
+        press(&mut line, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(line.text(), "one ");
+        // Words before the cursor only
+        let mut line = editor("one two");
+        press(&mut line, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut line, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(line.text(), "one o");
+    }
+
+    #[test]
+    fn deletes_to_the_start() {
+        let mut line = editor("one two");
+        for _ in 0..3 {
+            press(&mut line, KeyCode::Left, KeyModifiers::NONE);
+        }
+        press(&mut line, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(line.text(), "two");
+        assert!(line.at_start());
+    }
+
+    #[test]
+    fn browses_the_history() {
+        let mut history = History::default();
+        for entry in ["first", "second", " first ", "", "third"] {
+            history.add(entry).unwrap();
+        }
+        // Duplicates move to the end, blank entries are skipped
+        assert_eq!(history.entries, ["second", "first", "third"]);
+
+        assert_eq!(history.newer(), None);
+        assert_eq!(history.older("draft").as_deref(), Some("third"));
+        assert_eq!(history.older("third").as_deref(), Some("first"));
+        assert_eq!(history.older("first").as_deref(), Some("second"));
+        assert_eq!(history.older("second"), None);
+        assert_eq!(history.newer().as_deref(), Some("first"));
+        assert_eq!(history.newer().as_deref(), Some("third"));
+        assert_eq!(history.newer().as_deref(), Some("draft"));
+        assert_eq!(history.newer(), None);
+    }
+
+    #[test]
+    fn keeps_the_newest_entries() {
+        let mut history = History::default();
+        for i in 0..HISTORY_SIZE + 10 {
+            history.add(&i.to_string()).unwrap();
+        }
+        assert_eq!(history.entries.len(), HISTORY_SIZE);
+        assert_eq!(history.entries[0], "10");
+        assert_eq!(history.older("").as_deref(), Some("509"));
+    }
+}
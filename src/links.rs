@@ -0,0 +1,413 @@
+// Graph of the links between the notes of a vault
+//
+// Wikilinks resolve, ignoring case, against the path of a note when they
+// contain a `/`, then its file name, its title and its aliases. When several
+// notes fit, the one in the directory of the linking note wins, then the
+// shortest path. Markdown links resolve against the directory of the
+// linking note.
+//
+// Unlinked mentions are the places where a note names another one in plain
+// text, by its title or an alias, without linking to it.
+
+use crate::note::hashtags::fence_marker;
+use crate::note::links::{self, Link, LinkKind};
+use crate::note::{Note, split_frontmatter};
+use chrono::TimeZone;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// A link of a note and the note it resolves to, as indices in the notes
+/// the graph was built from
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub source: usize,
+    /// `None` when no note fits the link
+    pub target: Option<usize>,
+    pub link: Link,
+}
+
+#[derive(Debug, Default)]
+pub struct LinkGraph {
+    /// Paths of the notes, by index
+    pub paths: Vec<PathBuf>,
+    /// Every link of every note, by source note then order of appearance
+    pub edges: Vec<Edge>,
+    /// Index of each note, by path
+    indices: HashMap<PathBuf, usize>,
+    /// Edges leaving and reaching each note
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+}
+
+/// `path` with its `.` and `..` components applied, without touching the
+/// file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Lowercase path of a note without its extension, with `/` separators
+fn path_key(path: &Path) -> String {
+    path.with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/")
+        .to_lowercase()
+}
+
+/// Lowercase directory of a note, with `/` separators
+fn directory_key(path: &Path) -> String {
+    let directory = path.parent().unwrap_or(Path::new(""));
+    directory.to_string_lossy().replace('\\', "/").to_lowercase()
+}
+
+/// Notes by the names wikilinks can use for them
+struct Resolver {
+    by_path: HashMap<PathBuf, usize>,
+    by_name: HashMap<String, Vec<usize>>,
+    by_title: HashMap<String, Vec<usize>>,
+    by_alias: HashMap<String, Vec<usize>>,
+    /// Lowercase paths of the notes without extension, and of their
+    /// directories
+    keys: Vec<String>,
+    directories: Vec<String>,
+}
+
+impl Resolver {
+    fn new<Tz: TimeZone>(notes: &[Note<Tz>]) -> Self {
+        let mut resolver = Resolver {
+            by_path: HashMap::new(),
+            by_name: HashMap::new(),
+            by_title: HashMap::new(),
+            by_alias: HashMap::new(),
+            keys: vec![],
+            directories: vec![],
+        };
+        for (i, note) in notes.iter().enumerate() {
+            resolver.by_path.insert(normalize(&note.path), i);
+            let stem = note.path.file_stem().unwrap_or_default();
+            resolver
+                .by_name
+                .entry(stem.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(i);
+            resolver
+                .by_title
+                .entry(note.metadata.title.to_lowercase())
+                .or_default()
+                .push(i);
+            for alias in &note.metadata.aliases {
+                resolver.by_alias.entry(alias.to_lowercase()).or_default().push(i);
+            }
+            resolver.keys.push(path_key(&note.path));
+            resolver.directories.push(directory_key(&note.path));
+        }
+        resolver
+    }
+
+    /// Best of the notes `candidates` for a link of the note at `source`
+    fn closest(&self, candidates: &[usize], source: &Path) -> Option<usize> {
+        let directory = directory_key(source);
+        candidates.iter().copied().min_by_key(|&i| {
+            let path = &self.keys[i];
+            (self.directories[i] != directory, path.len(), path.clone())
+        })
+    }
+
+    fn resolve(&self, link: &Link, source: &Path) -> Option<usize> {
+        match link.kind {
+            LinkKind::Markdown => {
+                let directory = source.parent().unwrap_or(Path::new(""));
+                self.by_path.get(&normalize(&directory.join(&link.target))).copied()
+            }
+            LinkKind::Wiki => {
+                let target = link.target.to_lowercase();
+                let target = target.strip_suffix(".md").unwrap_or(&target);
+                if target.contains('/') {
+                    let target = target.trim_start_matches('/');
+                    let suffix = format!("/{}", target);
+                    // Paths are relative when the root is
+                    let candidates: Vec<usize> = (0..self.keys.len())
+                        .filter(|&i| self.keys[i] == target || self.keys[i].ends_with(&suffix))
+                        .collect();
+                    return self.closest(&candidates, source);
+                }
+                [&self.by_name, &self.by_title, &self.by_alias]
+                    .iter()
+                    .find_map(|names| self.closest(names.get(target)?, source))
+            }
+        }
+    }
+}
+
+impl LinkGraph {
+    /// Graph of the links of `notes`, read from their content
+    pub fn build<Tz: TimeZone>(notes: &[Note<Tz>]) -> Self {
+        let resolver = Resolver::new(notes);
+        let mut graph = LinkGraph {
+            paths: notes.iter().map(|note| note.path.clone()).collect(),
+            edges: vec![],
+            indices: notes
+                .iter()
+                .enumerate()
+                .map(|(i, note)| (note.path.clone(), i))
+                .collect(),
+            outgoing: vec![vec![]; notes.len()],
+            incoming: vec![vec![]; notes.len()],
+        };
+        for (source, note) in notes.iter().enumerate() {
+            for link in links::find(&note.content) {
+                let target = resolver.resolve(&link, &note.path);
+                let edge = graph.edges.len();
+                graph.outgoing[source].push(edge);
+                if let Some(target) = target {
+                    graph.incoming[target].push(edge);
+                }
+                graph.edges.push(Edge {
+                    source,
+                    target,
+                    link,
+                });
+            }
+        }
+        graph
+    }
+
+    /// Index of the note at `path`
+    pub fn index_of(&self, path: &Path) -> Option<usize> {
+        self.indices.get(path).copied()
+    }
+
+    /// Links of the note `note`
+    pub fn outgoing(&self, note: usize) -> impl Iterator<Item = &Edge> {
+        self.outgoing[note].iter().map(|&edge| &self.edges[edge])
+    }
+
+    /// Links of other notes resolving to the note `note`
+    pub fn backlinks(&self, note: usize) -> impl Iterator<Item = &Edge> {
+        self.incoming[note]
+            .iter()
+            .map(|&edge| &self.edges[edge])
+            .filter(move |edge| edge.source != note)
+    }
+
+    /// Links no note fits
+    pub fn unresolved(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(|edge| edge.target.is_none())
+    }
+
+    /// Whether the note `note` neither links to nor is linked from another
+    /// note
+    pub fn is_isolated(&self, note: usize) -> bool {
+        self.backlinks(note).next().is_none()
+            && self
+                .outgoing(note)
+                .all(|edge| edge.target.is_none_or(|target| target == note))
+    }
+}
+
+/// Plain text mentions of the names of a note: its title and aliases
+pub struct Mentions {
+    regex: Regex,
+}
+
+impl Mentions {
+    /// Matcher of `names` as whole words, ignoring case, `None` without a
+    /// name to look for
+    pub fn new(names: &[String]) -> Option<Self> {
+        let mut names: Vec<&str> = names
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+        // Longest names first, so that they win over the names they contain
+        names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        // Whole words, but `\b` only holds next to a word character: a name
+        // starting or ending with another one has no boundary on that side
+        let boundary = |c: Option<char>| {
+            if c.is_some_and(|c| c.is_alphanumeric() || c == '_') { r"\b" } else { "" }
+        };
+        let pattern = names
+            .iter()
+            .map(|name| {
+                let (start, end) = (name.chars().next(), name.chars().next_back());
+                format!("{}{}{}", boundary(start), regex::escape(name), boundary(end))
+            })
+            .collect::<Vec<_>>()
+            .join("|");
+        let regex = RegexBuilder::new(&format!("(?:{})", pattern))
+            .case_insensitive(true)
+            .build()
+            .ok()?;
+        Some(Self { regex })
+    }
+
+    /// Lines of `content` mentioning the names outside its frontmatter,
+    /// fenced code blocks and `links` (those of `content`), from 1
+    pub fn lines<'a>(
+        &self,
+        content: &str,
+        links: impl IntoIterator<Item = &'a Link>,
+    ) -> Vec<usize> {
+        if !self.regex.is_match(content) {
+            return vec![];
+        }
+        let links: Vec<&Link> = links.into_iter().collect();
+        let body_offset = content.len() - split_frontmatter(content).1.len();
+        let mut lines: Vec<usize> = vec![];
+        let mut fence: Option<&str> = None;
+        let mut offset = 0;
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            let line_offset = offset;
+            offset += line.len();
+            if line_offset < body_offset {
+                continue;
+            }
+            if let Some(marker) = fence {
+                if line.trim_start().starts_with(marker) {
+                    fence = None;
+                }
+                continue;
+            }
+            if let Some(marker) = fence_marker(line) {
+                fence = Some(marker);
+                continue;
+            }
+            let mentioned = self.regex.find_iter(line).any(|found| {
+                let span = line_offset + found.start()..line_offset + found.end();
+                !links
+                    .iter()
+                    .any(|link| link.span.start < span.end && span.start < link.span.end)
+            });
+            if mentioned {
+                lines.push(i + 1);
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::test_note;
+
+    /// Targets of the links of each note, as paths
+    fn targets(notes: &[(&str, &str)]) -> Vec<Vec<Option<String>>> {
+        let notes: Vec<_> = notes.iter().map(|(path, content)| test_note(path, content)).collect();
+        let graph = LinkGraph::build(&notes);
+        (0..notes.len())
+            .map(|note| {
+                graph
+                    .outgoing(note)
+                    .map(|edge| edge.target.map(|target| graph.paths[target].display().to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resolves_links() {
+        let targets = targets(&[
+            ("projects/Alpha.md", "---\ntitle: Project Alpha\naliases: [PA]\n---\n"),
+            (
+                "projects/Beta.md",
+                concat!(
+                    "[[alpha]] [[Project Alpha#Goals]] [[pa]] [[Alpha.md]] [[projects/alpha]] ",
+                    "[[Missing]] [a](Alpha.md) [up](../Index.md) [index](./../Index.md)",
+                ),
+            ),
+            ("Index.md", "# Home\n[[beta]] [[Alpha]] [[home]] [[archive/alpha]]"),
+            ("archive/Alpha.md", ""),
+        ]);
+        let path = |path: &str| Some(path.to_string());
+        assert_eq!(
+            targets[1],
+            [
+                path("projects/Alpha.md"),
+                path("projects/Alpha.md"),
+                path("projects/Alpha.md"),
+                path("projects/Alpha.md"),
+                path("projects/Alpha.md"),
+                None,
+                path("projects/Alpha.md"),
+                path("Index.md"),
+                path("Index.md"),
+            ]
+        );
+        // Out of the directory of both `Alpha` notes, the shortest path wins
+        assert_eq!(
+            targets[2],
+            [path("projects/Beta.md"), path("archive/Alpha.md"), path("Index.md"), path("archive/Alpha.md")]
+        );
+    }
+
+    #[test]
+    fn finds_backlinks() {
+        let notes: Vec<_> = [
+            ("a.md", "[[b]] [[b#Part]] [[a]]"),
+            ("b.md", "[[a]] [[missing]]"),
+            ("c.md", "[c](b.md)"),
+            ("d.md", "`[[a]]`"),
+        ]
+        .iter()
+        .map(|(path, content)| test_note(path, content))
+        .collect();
+        let graph = LinkGraph::build(&notes);
+        let backlinks = |note| graph.backlinks(note).map(|edge| edge.source).collect::<Vec<_>>();
+        // Self-links are left out
+        assert_eq!(backlinks(0), [1]);
+        assert_eq!(backlinks(1), [0, 0, 2]);
+        assert!(backlinks(2).is_empty());
+        assert_eq!(graph.unresolved().map(|edge| edge.link.target.as_str()).collect::<Vec<_>>(), ["missing"]);
+        assert!(!graph.is_isolated(2));
+        assert!(graph.is_isolated(3));
+    }
+
+    #[test]
+    fn finds_unlinked_mentions() {
+        let names = [String::from("Project Alpha"), String::from("PA"), String::from(" ")];
+        let mentions = Mentions::new(&names).unwrap();
+        let content = "\
+---
+title: project alpha
+---
+About project alpha.
+See [[Project Alpha]] or [Project Alpha](alpha.md), but PA too.
+PAPA and alphas
+```
+Project Alpha
+```
+[[Project Alpha|PA]]
+";
+        let links = links::find(content);
+        assert_eq!(mentions.lines(content, &links), [4, 5]);
+        assert_eq!(Mentions::new(&names[..1]).unwrap().lines(content, &links), [4]);
+        assert!(Mentions::new(&[String::new()]).is_none());
+        // A name made of other characters than words
+        let mentions = Mentions::new(&[String::from("C++ (notes)")]).unwrap();
+        assert_eq!(mentions.lines("Using c++ (notes) daily", []), [1]);
+    }
+
+    #[test]
+    fn resolves_file_names_before_titles_and_aliases() {
+        let targets = targets(&[
+            ("a.md", "---\ntitle: b\n---\n[[b]] [[c]]"),
+            ("b.md", "---\naliases: [c]\n---\n"),
+            ("c.md", "---\ntitle: Other\n---\n"),
+        ]);
+        assert_eq!(targets[0], [Some("b.md".to_string()), Some("c.md".to_string())]);
+    }
+}
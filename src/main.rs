@@ -1,29 +1,89 @@
+pub mod bm25;
 pub mod cli;
+pub mod config;
 pub mod editor;
+pub mod fuzzy;
+pub mod index;
+pub mod journal;
+pub mod line_editor;
+pub mod links;
 pub mod note;
+pub mod prompt;
+pub mod query;
 pub mod search;
+pub mod stats;
+pub mod tags;
+pub mod template;
 pub mod traversal;
 
 use std::io;
+use std::process::ExitCode;
 
 use clap::Parser;
-use noteorg::cli::list_files;
 
-use crate::cli::{edit_file, generate_completions, show_search_results_realtime};
+use crate::cli::{
+    edit_file, find_notes, generate_completions, list_files, manage_tags, manage_templates, new_note,
+    open_journal, show_backlinks, show_search_results_realtime, show_statistics, tag_notes,
+};
+use crate::journal::Period;
+use crate::config::Config;
 
-fn main() -> io::Result<()> {
-    let args = cli::Args::parse();
+fn main() -> ExitCode {
+    match run(cli::Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            // Displayed rather than debug-printed, messages may span lines
+            eprintln!("Error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: cli::Args) -> io::Result<()> {
+    // Completions need no configuration, a broken one must not prevent them
+    if let cli::Commands::Completions { shell } = &args.cmd {
+        generate_completions(shell.clone());
+        return Ok(());
+    }
+    let mut config = Config::load(args.config.as_deref())?;
+    if let Some(root) = &args.root {
+        config.set_root(root);
+    }
     match args.cmd {
-        cli::Commands::List { path } => list_files(path),
-        cli::Commands::Edit { search_value } => edit_file(search_value),
-        cli::Commands::Search => show_search_results_realtime(),
-        cli::Commands::Completions { shell } => {
-            generate_completions(shell);
-            Ok(())
+        cli::Commands::List { path, filter } => list_files(path, filter.as_deref(), &config),
+        cli::Commands::Edit { search_value } => edit_file(search_value, &config),
+        cli::Commands::Search { query, mode } => {
+            show_search_results_realtime(query.as_deref(), mode, &config)
+        }
+        cli::Commands::Find { query, limit } => find_notes(&query.join(" "), limit, &config),
+        cli::Commands::New(new_args) => new_note(&new_args, &config),
+        cli::Commands::Today { no_edit } => open_journal("today", Period::Day, no_edit, &config),
+        cli::Commands::Yesterday { no_edit } => {
+            open_journal("yesterday", Period::Day, no_edit, &config)
+        }
+        cli::Commands::Journal {
+            date,
+            week,
+            month,
+            no_edit,
+        } => {
+            let period = if week {
+                Period::Week
+            } else if month {
+                Period::Month
+            } else {
+                Period::Day
+            };
+            open_journal(date.as_deref().unwrap_or("today"), period, no_edit, &config)
         }
-        _ => {
-            println!("Not implemented!");
-            Ok(())
+        cli::Commands::Templates { action } => manage_templates(&action, &config),
+        cli::Commands::Tags { action } => manage_tags(action.as_ref(), &config),
+        cli::Commands::Tag { action } => tag_notes(&action, &config),
+        cli::Commands::Backlinks { query } => show_backlinks(&query, &config),
+        cli::Commands::Statistics { json, filter } => {
+            show_statistics(json, filter.as_deref(), &config)
         }
+        // Generated above, before loading the configuration
+        cli::Commands::Completions { .. } => Ok(()),
     }
 }
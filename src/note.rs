@@ -1,40 +1,465 @@
 // Notes abstractions
 
-use chrono::{DateTime, TimeZone, Utc};
+pub mod frontmatter;
+pub mod hashtags;
+pub mod links;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use gray_matter::Matter;
 use gray_matter::engine::YAML;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
 use std::ffi::OsStr;
-use std::fs::{metadata, read_to_string};
-use std::io;
-use std::path::{Path, PathBuf};
+use std::fs::{self, metadata, read_to_string};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-#[derive(Deserialize, Debug)]
-#[allow(dead_code)]
+/// Where the title of a note comes from, tried in the configured order
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleSource {
+    /// The frontmatter `title`
+    Frontmatter,
+    /// The first `# Heading` of the body
+    Heading,
+    /// The file name without its extension
+    Filename,
+}
+
+impl TitleSource {
+    pub fn default_order() -> Vec<Self> {
+        vec![Self::Frontmatter, Self::Heading, Self::Filename]
+    }
+}
+
+/// Frontmatter keys with a meaning for noteorg, the other keys being kept in
+/// `extra`. Scalars are accepted where strings are expected, and a single
+/// string where a list is.
+#[derive(Deserialize, Serialize, Debug)]
 pub struct NoteFrontMatter {
+    #[serde(default, deserialize_with = "lenient_string", skip_serializing_if = "Option::is_none")]
     title: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list", skip_serializing_if = "Option::is_none")]
     tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient_string", skip_serializing_if = "Option::is_none")]
     date: Option<String>,
+    #[serde(default, deserialize_with = "lenient_list", skip_serializing_if = "Option::is_none")]
+    aliases: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient_string", skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string", skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(flatten)]
+    extra: Mapping,
+}
+
+/// A YAML scalar as a string
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    }
+}
+
+fn lenient_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(scalar_string(&Value::deserialize(deserializer)?))
+}
+
+fn lenient_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Sequence(items) => Some(items.iter().filter_map(scalar_string).collect()),
+        other => scalar_string(&other).map(|item| vec![item]),
+    })
+}
+
+/// Value with every mapping key turned into a string, so that it can be
+/// stored in the JSON index
+fn with_string_keys(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => Value::Mapping(string_keys(mapping)),
+        Value::Sequence(items) => Value::Sequence(items.into_iter().map(with_string_keys).collect()),
+        other => other,
+    }
+}
+
+fn string_keys(mapping: Mapping) -> Mapping {
+    mapping
+        .into_iter()
+        .map(|(key, value)| {
+            let key = match key {
+                Value::String(key) => key,
+                other => scalar_string(&other).unwrap_or_else(|| {
+                    serde_yaml::to_string(&other)
+                        .map(|yaml| yaml.trim_end().to_string())
+                        .unwrap_or_default()
+                }),
+            };
+            (Value::String(key), with_string_keys(value))
+        })
+        .collect()
+}
+
+/// Date of a frontmatter `date`: RFC 3339 or RFC 2822, a date and time
+/// without offset taken in `tz`, or a date alone at midnight
+fn parse_frontmatter_date<Tz: TimeZone>(value: &str, tz: &Tz) -> Option<DateTime<Tz>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value).or_else(|_| DateTime::parse_from_rfc2822(value)) {
+        return Some(date.with_timezone(tz));
+    }
+    let local = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y", "%B %d, %Y", "%d %B %Y", "%b %d, %Y", "%d %b %Y"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+            .map(|date| date.and_time(Default::default()))
+    })?;
+    tz.from_local_datetime(&local).earliest()
+}
+
+impl NoteFrontMatter {
+    pub fn new(title: &str, tags: Vec<String>, date: String) -> Self {
+        Self {
+            title: Some(title.to_string()),
+            tags: if tags.is_empty() { None } else { Some(tags) },
+            date: Some(date),
+            aliases: None,
+            status: None,
+            id: None,
+            extra: Mapping::new(),
+        }
+    }
+
+    /// Frontmatter block, delimiters included
+    pub fn to_block(&self) -> io::Result<String> {
+        let yaml = serde_yaml::to_string(self).map_err(io::Error::other)?;
+        Ok(format!("---\n{}---\n", yaml))
+    }
+}
+
+/// Where a tag of a note was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagSource {
+    Frontmatter,
+    /// A `#hashtag` of the body
+    Inline,
+    Both,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    /// Name without `#`, nested tags being separated by `/`
+    pub name: String,
+    pub source: TagSource,
+}
+
+impl Tag {
+    /// Whether the tag is `name` or nested below it, ignoring case:
+    /// `project/alpha` matches `project` and `project/alpha`
+    pub fn matches(&self, name: &str) -> bool {
+        let tag = self.name.to_lowercase();
+        let name = name.to_lowercase();
+        tag == name || tag.starts_with(&format!("{}/", name))
+    }
+}
+
+/// Frontmatter tags followed by the hashtags of the body not among them
+fn merge_tags(frontmatter: Vec<String>, inline: Vec<String>) -> Vec<Tag> {
+    let mut tags: Vec<Tag> = vec![];
+    for name in frontmatter {
+        let name = name.trim().trim_start_matches('#').to_string();
+        if !name.is_empty() && !tags.iter().any(|tag| tag.name.to_lowercase() == name.to_lowercase()) {
+            tags.push(Tag {
+                name,
+                source: TagSource::Frontmatter,
+            });
+        }
+    }
+    for name in inline {
+        match tags.iter_mut().find(|tag| tag.name.to_lowercase() == name.to_lowercase()) {
+            Some(tag) => tag.source = TagSource::Both,
+            None => tags.push(Tag {
+                name,
+                source: TagSource::Inline,
+            }),
+        }
+    }
+    tags
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "DateTime<Tz>: Serialize",
+    deserialize = "DateTime<Tz>: Deserialize<'de>"
+))]
 #[allow(dead_code)]
 pub struct NoteMetadata<Tz: TimeZone> {
     pub filename: String,
     pub title: String,
-    pub tags: Vec<String>,
+    /// Frontmatter tags and inline hashtags
+    pub tags: Vec<Tag>,
     pub category: Vec<String>,
+    /// Frontmatter `date` when present and valid, else the file creation time
     pub date_created: DateTime<Tz>,
     pub date_last_modified: DateTime<Tz>,
+    pub aliases: Vec<String>,
+    pub status: Option<String>,
+    pub id: Option<String>,
+    /// Frontmatter keys not listed above
+    pub extra: Mapping,
 }
 
-#[derive(Debug)]
+impl<Tz: TimeZone> NoteMetadata<Tz> {
+    pub fn tag_names(&self) -> Vec<&str> {
+        self.tags.iter().map(|tag| tag.name.as_str()).collect()
+    }
+
+    /// Same metadata with its dates expressed in another timezone
+    pub fn with_timezone<Tz2: TimeZone>(&self, tz: &Tz2) -> NoteMetadata<Tz2> {
+        NoteMetadata {
+            filename: self.filename.clone(),
+            title: self.title.clone(),
+            tags: self.tags.clone(),
+            category: self.category.clone(),
+            date_created: self.date_created.with_timezone(tz),
+            date_last_modified: self.date_last_modified.with_timezone(tz),
+            aliases: self.aliases.clone(),
+            status: self.status.clone(),
+            id: self.id.clone(),
+            extra: self.extra.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "DateTime<Tz>: Serialize",
+    deserialize = "DateTime<Tz>: Deserialize<'de>"
+))]
 pub struct Note<Tz: TimeZone> {
     pub metadata: NoteMetadata<Tz>,
     pub content: String,
     pub path: PathBuf,
 }
 
+impl<Tz: TimeZone> Note<Tz> {
+    /// Same note with its dates expressed in another timezone
+    pub fn with_timezone<Tz2: TimeZone>(&self, tz: &Tz2) -> Note<Tz2> {
+        Note {
+            metadata: self.metadata.with_timezone(tz),
+            content: self.content.clone(),
+            path: self.path.clone(),
+        }
+    }
+
+    /// Note content without its frontmatter block
+    pub fn body(&self) -> &str {
+        split_frontmatter(&self.content).1
+    }
+}
+
+/// Split a document into its YAML frontmatter (without the `---`
+/// delimiters) and its body. The frontmatter is `None` when the document
+/// does not start with a complete block.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+/// Text of the first level one ATX heading (`# Title`) outside code blocks
+fn first_heading(body: &str) -> Option<String> {
+    let mut fence: Option<&str> = None;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+            fence = Some(marker);
+            continue;
+        }
+        // Four spaces of indentation make a code block
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        let Some(text) = trimmed.strip_prefix('#') else {
+            continue;
+        };
+        if !text.is_empty() && !text.starts_with([' ', '\t']) {
+            continue;
+        }
+        // An optional closing sequence of `#` must follow a space
+        let text = text.trim();
+        let without_closing = text.trim_end_matches('#');
+        let text = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+            without_closing.trim_end()
+        } else {
+            text
+        };
+        if !text.is_empty() {
+            return Some(text.to_string());
+        }
+    }
+    None
+}
+
+/// Title of a note from the first of `sources` providing one, falling back
+/// to the file name so that it is never empty
+fn resolve_title(
+    sources: &[TitleSource],
+    frontmatter: Option<&str>,
+    body: &str,
+    path: &Path,
+) -> String {
+    let file_stem = || {
+        path.file_stem()
+            .unwrap_or(OsStr::new("Empty"))
+            .to_string_lossy()
+            .into_owned()
+    };
+    sources
+        .iter()
+        .find_map(|source| match source {
+            TitleSource::Frontmatter => frontmatter
+                .map(str::trim)
+                .filter(|title| !title.is_empty())
+                .map(String::from),
+            TitleSource::Heading => first_heading(body),
+            TitleSource::Filename => Some(file_stem()),
+        })
+        .unwrap_or_else(file_stem)
+}
+
+/// Turn a title into a file name friendly slug, e.g. "Weekly Sync #3" into
+/// "weekly-sync-3"
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        String::from("untitled")
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Directory of a category below the notes root, refusing categories that
+/// would escape it
+pub fn category_dir(root: &Path, category: &str) -> io::Result<PathBuf> {
+    let category = Path::new(category.trim_matches('/'));
+    if !category
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid category {:?}", category),
+        ));
+    }
+    Ok(root.join(category))
+}
+
+/// Move a note into `dir`, creating it. Fails rather than replacing an
+/// existing note.
+pub fn move_note(path: &Path, dir: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} is not a file", path))
+    })?;
+    let destination = dir.join(file_name);
+    if destination == path {
+        return Ok(destination);
+    }
+    if destination.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Cannot move {:?}: {:?} already exists", path, destination),
+        ));
+    }
+    fs::create_dir_all(dir)?;
+    fs::rename(path, &destination)
+        .map_err(|e| io::Error::new(e.kind(), format!("Cannot move {:?}: {}", path, e)))?;
+    Ok(destination)
+}
+
+/// Notes written by this process, numbering their temporary files
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// Replace the content of an existing note atomically
+pub fn write_note(path: &Path, content: &str) -> io::Result<()> {
+    let error = |e: io::Error| io::Error::new(e.kind(), format!("Cannot write {:?}: {}", path, e));
+    // Written next to the note then renamed over it, so that an interrupted
+    // write never leaves a truncated note. The `.tmp` extension keeps it out
+    // of the notes while it exists, and its name is unique to this write so
+    // that concurrent writes of the note do not share it.
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let write = WRITES.fetch_add(1, Ordering::Relaxed);
+    let temporary = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        write
+    ));
+    if let Err(e) = fs::write(&temporary, content) {
+        let _ = fs::remove_file(&temporary);
+        return Err(error(e));
+    }
+    if let Ok(metadata) = fs::metadata(path) {
+        let _ = fs::set_permissions(&temporary, metadata.permissions());
+    }
+    fs::rename(&temporary, path).map_err(|e| {
+        let _ = fs::remove_file(&temporary);
+        error(e)
+    })
+}
+
+/// Write a new note, creating its parent directories. Fails if the file
+/// already exists.
+pub fn create_note(path: &Path, frontmatter: &NoteFrontMatter, body: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Cannot create {:?}: {}", path, e)))?;
+    write!(file, "{}\n{}", frontmatter.to_block()?, body)?;
+    Ok(())
+}
+
 pub fn read_note_metadata<Tz: TimeZone>(
     path: &Path,
     root_path: &Path,
@@ -49,7 +474,10 @@ pub fn read_note_metadata<Tz: TimeZone>(
         let metadata = metadata(path)?;
         let last_modified_utc: DateTime<Utc> = metadata.modified()?.into();
         let last_modified = last_modified_utc.with_timezone(tz);
-        let created_utc: DateTime<Utc> = metadata.created()?.into();
+        // Not every filesystem records creation times
+        let created_utc: DateTime<Utc> = metadata
+            .created()
+            .map_or(last_modified_utc, DateTime::from);
         let created = created_utc.with_timezone(tz);
         let title: String = path
             .file_name()
@@ -69,26 +497,242 @@ pub fn read_note_metadata<Tz: TimeZone>(
                 .collect(),
             date_created: created,
             date_last_modified: last_modified,
+            aliases: vec![],
+            status: None,
+            id: None,
+            extra: Mapping::new(),
         })
     }
 }
 
-pub fn read_note<Tz: TimeZone>(path: &Path, root_path: &Path, tz: &Tz) -> io::Result<Note<Tz>> {
+pub fn read_note<Tz: TimeZone>(
+    path: &Path,
+    root_path: &Path,
+    tz: &Tz,
+    title_from: &[TitleSource],
+) -> io::Result<Note<Tz>> {
     let mut note = Note {
         metadata: read_note_metadata(path, root_path, tz)?,
         content: read_to_string(path)?,
         path: path.to_path_buf(),
     };
+    read_content(&mut note, tz, title_from);
+    Ok(note)
+}
+
+/// Fill the metadata of `note` read from its frontmatter and body
+fn read_content<Tz: TimeZone>(note: &mut Note<Tz>, tz: &Tz, title_from: &[TitleSource]) {
     // Parsing frontmatter
     let matter = Matter::<YAML>::new();
     let frontmatter: Option<NoteFrontMatter> = matter
         .parse::<NoteFrontMatter>(note.content.as_str())
         .ok()
         .and_then(|info| info.data);
+    let mut frontmatter_title = None;
+    let mut frontmatter_tags = vec![];
     if let Some(data) = frontmatter {
-        note.metadata.tags = data.tags.unwrap_or_default();
-        note.metadata.title = data.title.unwrap_or_default();
+        frontmatter_tags = data.tags.unwrap_or_default();
+        frontmatter_title = data.title;
+        if let Some(date) = data.date.as_deref().and_then(|date| parse_frontmatter_date(date, tz)) {
+            note.metadata.date_created = date;
+        }
+        note.metadata.aliases = data.aliases.unwrap_or_default();
+        note.metadata.status = data.status;
+        note.metadata.id = data.id;
+        note.metadata.extra = string_keys(data.extra);
     }
+    note.metadata.title =
+        resolve_title(title_from, frontmatter_title.as_deref(), note.body(), &note.path);
+    note.metadata.tags = merge_tags(frontmatter_tags, hashtags::names(note.body()));
+}
 
-    Ok(note)
+/// Note at `path` (relative to the root) with the content `content`, dated
+/// 2026-01-01, for the tests of the modules working on notes
+#[cfg(test)]
+pub(crate) fn test_note(path: &str, content: &str) -> Note<Utc> {
+    let date = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    let path = PathBuf::from(path);
+    let filename = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let mut note = Note {
+        metadata: NoteMetadata {
+            title: filename.clone(),
+            filename,
+            tags: vec![],
+            category: path
+                .parent()
+                .into_iter()
+                .flat_map(Path::iter)
+                .map(|x| x.to_string_lossy().into_owned())
+                .collect(),
+            date_created: date,
+            date_last_modified: date,
+            aliases: vec![],
+            status: None,
+            id: None,
+            extra: Mapping::new(),
+        },
+        content: content.to_string(),
+        path,
+    };
+    read_content(&mut note, &Utc, &TitleSource::default_order());
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn reads_the_first_heading() {
+        let body = "\
+```md
+# Fenced
+```
+    # Indented code
+#NotAHeading
+## Second level
+# First #
+";
+        // Only level 1 headings give titles
+        assert_eq!(first_heading(body).as_deref(), Some("First"));
+        for (line, heading) in [
+            ("# Title", Some("Title")),
+            ("   #   Spaced   ", Some("Spaced")),
+            ("# C# ##", Some("C#")),
+            ("# Issue #12", Some("Issue #12")),
+            ("# Trailing#", Some("Trailing#")),
+            ("# ###", None),
+            ("#", None),
+            ("## Two", None),
+        ] {
+            assert_eq!(first_heading(line).as_deref(), heading, "{:?}", line);
+        }
+        // An unclosed fence hides the rest of the body
+        assert_eq!(first_heading("~~~\n# Hidden\n"), None);
+    }
+
+    #[test]
+    fn resolves_titles_in_order() {
+        let title = |content: &str| test_note("dir/file-name.md", content).metadata.title;
+        assert_eq!(title("---\ntitle: Set\n---\n# Heading\n"), "Set");
+        for empty in ["title:", "title: ''", "title: '  '", "tags: [a]"] {
+            assert_eq!(title(&format!("---\n{}\n---\n# Heading\n", empty)), "Heading");
+        }
+        assert_eq!(title("---\ntitle:\n---\nNo heading\n"), "file-name");
+        assert_eq!(title(""), "file-name");
+
+        let path = Path::new("dir/file-name.md");
+        let sources = [TitleSource::Heading, TitleSource::Frontmatter];
+        assert_eq!(resolve_title(&sources, Some("Set"), "# Heading", path), "Heading");
+        assert_eq!(resolve_title(&sources, None, "text", path), "file-name");
+        assert_eq!(resolve_title(&[TitleSource::Filename], Some("Set"), "# H", path), "file-name");
+    }
+
+    #[test]
+    fn slugifies_titles() {
+        for (title, slug) in [
+            ("Weekly Sync #3", "weekly-sync-3"),
+            ("  Hello,   World!  ", "hello-world"),
+            ("C++ & Rust: notes/ideas", "c-rust-notes-ideas"),
+            ("Café Über Straße", "café-über-straße"),
+            ("会议 记录", "会议-记录"),
+            ("ÉTÉ", "été"),
+            ("already-a-slug", "already-a-slug"),
+            ("--dashes--", "dashes"),
+            ("?!", "untitled"),
+            ("", "untitled"),
+        ] {
+            assert_eq!(slugify(title), slug, "{:?}", title);
+        }
+    }
+
+    #[test]
+    fn creates_notes_without_overwriting() {
+        let dir = std::env::temp_dir().join(format!("noteorg-{}-create", std::process::id()));
+        let path = dir.join("category/note.md");
+        let frontmatter = NoteFrontMatter::new("Note", vec![String::from("a")], String::from("2026-10-17"));
+        create_note(&path, &frontmatter, "Body\n").unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "---\ntitle: Note\ntags:\n- a\ndate: 2026-10-17\n---\n\nBody\n");
+
+        let other = NoteFrontMatter::new("Other", vec![], String::from("2026-10-18"));
+        let error = create_note(&path, &other, "Other body\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_notes_in_place() {
+        let dir = std::env::temp_dir().join(format!("noteorg-{}-write", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("note.md");
+        fs::write(&path, "old").unwrap();
+        write_note(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        // No temporary file is left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || write_note(&path, &format!("writer {}", i)))
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+        assert!(fs::read_to_string(&path).unwrap().starts_with("writer "));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn matches_nested_tags() {
+        let tag = Tag {
+            name: String::from("Project/Alpha"),
+            source: TagSource::Inline,
+        };
+        assert!(tag.matches("project"));
+        assert!(tag.matches("PROJECT/alpha"));
+        assert!(!tag.matches("proj"));
+        assert!(!tag.matches("project/alpha/beta"));
+        assert!(!tag.matches("alpha"));
+    }
+
+    #[test]
+    fn merges_frontmatter_and_inline_tags() {
+        let note = test_note("a.md", "---\ntags: [rust, '#cli']\n---\n#Rust #project/alpha\n");
+        let tags: Vec<(&str, TagSource)> =
+            note.metadata.tags.iter().map(|tag| (tag.name.as_str(), tag.source)).collect();
+        assert_eq!(
+            tags,
+            [
+                ("rust", TagSource::Both),
+                ("cli", TagSource::Frontmatter),
+                ("project/alpha", TagSource::Inline),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_frontmatter_dates() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let date = |value| parse_frontmatter_date(value, &tz).map(|date| date.to_rfc3339());
+        let midnight = Some(String::from("2026-09-01T00:00:00+02:00"));
+        for value in ["2026-09-01", " 2026/09/01 ", "01.09.2026", "September 01, 2026", "1 Sep 2026"] {
+            assert_eq!(date(value), midnight, "{}", value);
+        }
+        assert_eq!(date("2026-09-01 14:30"), Some(String::from("2026-09-01T14:30:00+02:00")));
+        assert_eq!(date("2026-09-01T14:30:05.5"), Some(String::from("2026-09-01T14:30:05.500+02:00")));
+        // Explicit offsets are converted to the configured timezone
+        assert_eq!(date("2026-09-01T12:00:00Z"), Some(String::from("2026-09-01T14:00:00+02:00")));
+        assert_eq!(
+            date("Tue, 1 Sep 2026 12:00:00 +0000"),
+            Some(String::from("2026-09-01T14:00:00+02:00"))
+        );
+        assert_eq!(date("next week"), None);
+        assert_eq!(date("2026-13-01"), None);
+    }
 }
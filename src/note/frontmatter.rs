@@ -0,0 +1,616 @@
+// Editing the frontmatter of existing notes
+//
+// Edits are made on the lines of the YAML block rather than by serializing
+// it again, so that the order of the keys, comments, quoting and the body of
+// the note are kept byte for byte. Only the lines of the edited key change:
+// list items are kept, replaced in place, removed or appended one by one.
+
+use super::split_frontmatter;
+use serde_yaml::Value;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// Strings of a YAML list, or of a single scalar
+fn strings(value: &Value) -> Vec<String> {
+    let scalar = |value: &Value| match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    };
+    match value {
+        Value::Sequence(items) => items.iter().filter_map(scalar).collect(),
+        other => scalar(other).into_iter().collect(),
+    }
+}
+
+/// Items of the list at `key`, from a YAML list or a single string
+pub fn list(content: &str, key: &str) -> Vec<String> {
+    let Some(yaml) = split_frontmatter(content).0 else {
+        return vec![];
+    };
+    let Ok(Value::Mapping(mapping)) = serde_yaml::from_str::<Value>(yaml) else {
+        return vec![];
+    };
+    mapping.get(key).map(strings).unwrap_or_default()
+}
+
+/// Tags of a document, from a YAML list or a single string
+pub fn tags(content: &str) -> Vec<String> {
+    list(content, "tags")
+}
+
+/// A string as a plain YAML scalar usable in a flow sequence, quoted when
+/// needed
+fn scalar(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'))
+        && !matches!(value, "true" | "false" | "null" | "yes" | "no" | "on" | "off")
+        && value.parse::<f64>().is_err()
+        // Such as `0x10` or `.inf`, read back as numbers
+        && serde_yaml::from_str::<Value>(value).is_ok_and(|read| read.as_str() == Some(value));
+    if plain {
+        value.to_string()
+    } else {
+        quoted(value, '"')
+    }
+}
+
+/// A string as a quoted YAML scalar
+fn quoted(value: &str, quote: char) -> String {
+    if quote == '\'' {
+        format!("'{}'", value.replace('\'', "''"))
+    } else {
+        // JSON strings are valid double quoted YAML scalars
+        serde_json::to_string(value).unwrap_or_else(|_| format!("{:?}", value))
+    }
+}
+
+/// Quote character of a raw scalar, `None` when it is plain
+fn quote_of(raw: &str) -> Option<char> {
+    raw.chars().next().filter(|c| matches!(c, '"' | '\''))
+}
+
+/// A string written like `example`: quoted the same way, or plain when
+/// possible
+fn written_like(value: &str, example: Option<&str>) -> String {
+    match example.and_then(quote_of) {
+        Some(quote) => quoted(value, quote),
+        None => scalar(value),
+    }
+}
+
+/// Value of a raw YAML scalar
+fn unquote(raw: &str) -> String {
+    match serde_yaml::from_str::<Value>(raw) {
+        Ok(Value::String(value)) if quote_of(raw).is_some() => value,
+        _ => raw.to_string(),
+    }
+}
+
+/// Split the text after a key or a list dash into its value and its
+/// trailing comment (with the spaces before it), ignoring `#` in quotes
+fn split_comment(text: &str) -> (&str, &str) {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            // Only a scalar starting with a quote is quoted
+            None if i == 0 && matches!(c, '"' | '\'') => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => {
+                let value = text[..i].trim_end();
+                return (value, &text[value.len()..]);
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    (text.trim_end(), &text[text.trim_end().len()..])
+}
+
+/// Newline used by the YAML block
+fn newline(yaml: &str) -> &'static str {
+    if yaml.contains("\r\n") { "\r\n" } else { "\n" }
+}
+
+/// Line without its line break
+fn chomp(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// The lines of a top level key of the YAML block
+struct Entry {
+    /// Byte range of the key line and of the lines continuing its value
+    range: Range<usize>,
+    /// Key as written, colon included
+    key: String,
+    /// Text after the colon on the key line, without its comment
+    value: String,
+    /// Comment of the key line, with the spaces before it
+    comment: String,
+    /// Lines continuing the value, line breaks included
+    continuation: Vec<String>,
+}
+
+fn find_entry(yaml: &str, key: &str) -> Option<Entry> {
+    let lines: Vec<&str> = yaml.split_inclusive('\n').collect();
+    let mut offset = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let start = offset;
+        offset += line.len();
+        let Some(rest) = [key.to_string(), format!("\"{}\"", key), format!("'{}'", key)]
+            .iter()
+            .find_map(|written| line.strip_prefix(written.as_str()))
+        else {
+            continue;
+        };
+        let Some(after) = rest.strip_prefix(':') else {
+            continue;
+        };
+        if !(chomp(after).is_empty() || after.starts_with([' ', '\t'])) {
+            continue;
+        }
+        let (value, comment) = split_comment(chomp(after).trim_start());
+        // Without a value, the spaces before the comment are its own
+        let comment = if value.is_empty() { chomp(after) } else { comment };
+
+        // Indented lines, `-` items and blank lines continue the value
+        let mut continuation: Vec<String> = lines[i + 1..]
+            .iter()
+            .take_while(|line| {
+                line.starts_with([' ', '\t'])
+                    || line.starts_with('-') && !line.starts_with("---")
+                    || line.trim().is_empty()
+            })
+            .map(|line| line.to_string())
+            .collect();
+        // Blank lines after the value belong to the next key
+        while continuation.last().is_some_and(|line| line.trim().is_empty()) {
+            continuation.pop();
+        }
+        let end = offset + continuation.iter().map(String::len).sum::<usize>();
+        return Some(Entry {
+            range: start..end,
+            key: line[..line.len() - after.len()].to_string(),
+            value: value.to_string(),
+            comment: comment.to_string(),
+            continuation,
+        });
+    }
+    None
+}
+
+/// Raw items of a one line flow sequence such as `[a, "b, c"]`, `None` when
+/// it is not one or nests other collections
+fn flow_items(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut items = vec![];
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    for c in inner.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if matches!(c, '"' | '\'') => quote = Some(c),
+            None if matches!(c, '[' | ']' | '{' | '}') => return None,
+            None if c == ',' => {
+                items.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            None => {}
+        }
+        current.push(c);
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if !current.trim().is_empty() || !items.is_empty() {
+        items.push(current.trim().to_string());
+    }
+    Some(items.into_iter().filter(|item| !item.is_empty()).collect())
+}
+
+/// An item line of a block sequence: its `  - ` prefix, raw value and comment
+fn block_item(line: &str) -> Option<(&str, &str, &str)> {
+    let line = chomp(line);
+    let dash = line.len() - line.trim_start().len();
+    let rest = line[dash..].strip_prefix('-')?;
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let value_start = line.len() - rest.trim_start().len();
+    let (raw, comment) = split_comment(&line[value_start..]);
+    Some((&line[..value_start], raw, comment))
+}
+
+/// How the items of a list change: for each current item the value it
+/// keeps or is replaced by (`None` to remove it), then the items appended.
+/// Removed items are replaced in order by new ones, so that a renamed item
+/// keeps its position.
+fn plan(current: &[String], wanted: &[String]) -> (Vec<Option<String>>, Vec<String>) {
+    let mut used = vec![false; wanted.len()];
+    let mut changes: Vec<Option<String>> = current
+        .iter()
+        .map(|item| {
+            let position = wanted
+                .iter()
+                .enumerate()
+                .position(|(i, w)| !used[i] && w == item)?;
+            used[position] = true;
+            Some(item.clone())
+        })
+        .collect();
+    let mut added = wanted
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|(item, _)| item.clone())
+        .collect::<Vec<_>>()
+        .into_iter();
+    for change in changes.iter_mut().filter(|change| change.is_none()) {
+        *change = added.next();
+    }
+    (changes, added.collect())
+}
+
+/// Lines of `entry` with its value replaced by the flow sequence `items`
+fn flow_entry(entry: &Entry, items: &[String], example: Option<&str>, newline: &str) -> String {
+    let items: Vec<String> = items.iter().map(|item| written_like(item, example)).collect();
+    format!("{} [{}]{}{}", entry.key, items.join(", "), entry.comment, newline)
+}
+
+/// Lines of `entry` with the list `items`, editing the current items in place
+fn edit_list(entry: &Entry, items: &[String], newline: &str) -> String {
+    // A block sequence: `key:` followed by `- item` lines
+    let block: Option<Vec<_>> = entry.value.is_empty().then(|| {
+        entry
+            .continuation
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| block_item(line).map(|item| (i, item)))
+            .collect()
+    });
+    if let Some(block) = block.filter(|block| !block.is_empty()) {
+        // Items spanning several lines are rewritten as a flow sequence
+        let simple = entry.continuation.iter().enumerate().all(|(i, line)| {
+            block.iter().any(|(j, _)| *j == i)
+                || line.trim().is_empty()
+                || line.trim_start().starts_with('#')
+        });
+        if simple {
+            let current: Vec<String> = block.iter().map(|(_, (_, raw, _))| unquote(raw)).collect();
+            let (changes, added) = plan(&current, items);
+            let (prefix, example) = (block[0].1.0.to_string(), block[0].1.1.to_string());
+            let last_item = block.last().map_or(0, |(i, _)| *i);
+            // An emptied list stays a list, its comments are kept
+            let empty = if items.is_empty() { " []" } else { "" };
+            let mut output = format!("{}{}{}{}", entry.key, empty, entry.comment, newline);
+            let mut changes = changes.into_iter();
+            for (i, line) in entry.continuation.iter().enumerate() {
+                match block.iter().find(|(j, _)| *j == i) {
+                    Some((_, (item_prefix, raw, comment))) => match changes.next().flatten() {
+                        Some(value) if value == unquote(raw) => output.push_str(line),
+                        Some(value) => output.push_str(&format!(
+                            "{}{}{}{}",
+                            item_prefix,
+                            written_like(&value, Some(raw)),
+                            comment,
+                            newline
+                        )),
+                        None => {}
+                    },
+                    None => output.push_str(line),
+                }
+                if i == last_item {
+                    for item in &added {
+                        output.push_str(&format!(
+                            "{}{}{}",
+                            prefix,
+                            written_like(item, Some(&example)),
+                            newline
+                        ));
+                    }
+                }
+            }
+            return output;
+        }
+    }
+
+    // A flow sequence on the key line
+    if let Some(raw_items) = flow_items(&entry.value).filter(|_| entry.continuation.is_empty()) {
+        let current: Vec<String> = raw_items.iter().map(|raw| unquote(raw)).collect();
+        let (changes, added) = plan(&current, items);
+        let example = raw_items.first().map(String::as_str);
+        let mut written: Vec<String> = raw_items
+            .iter()
+            .zip(changes)
+            .filter_map(|(raw, change)| {
+                let value = change?;
+                Some(if value == unquote(raw) {
+                    raw.clone()
+                } else {
+                    written_like(&value, Some(raw))
+                })
+            })
+            .collect();
+        written.extend(added.iter().map(|item| written_like(item, example)));
+        return format!("{} [{}]{}{}", entry.key, written.join(", "), entry.comment, newline);
+    }
+
+    let example = (!entry.value.is_empty()).then_some(entry.value.as_str());
+    flow_entry(entry, items, example, newline)
+}
+
+/// Document with the frontmatter YAML replaced by `edit(yaml)`, a block
+/// being added when there is none
+fn edit_yaml(content: &str, edit: impl FnOnce(&str) -> String) -> String {
+    match split_frontmatter(content).0 {
+        Some(yaml) => {
+            let start = yaml.as_ptr() as usize - content.as_ptr() as usize;
+            let end = start + yaml.len();
+            format!("{}{}{}", &content[..start], edit(yaml), &content[end..])
+        }
+        None => format!("---\n{}---\n\n{}", edit(""), content),
+    }
+}
+
+/// YAML with the lines of `key` replaced by `lines`, appended when the key
+/// is missing
+fn replace_entry(yaml: &str, key: &str, lines: impl FnOnce(Option<&Entry>) -> String) -> String {
+    match find_entry(yaml, key) {
+        Some(entry) => format!(
+            "{}{}{}",
+            &yaml[..entry.range.start],
+            lines(Some(&entry)),
+            &yaml[entry.range.end..]
+        ),
+        None => {
+            let mut yaml = yaml.to_string();
+            if !yaml.is_empty() && !yaml.ends_with('\n') {
+                yaml.push_str(newline(&yaml));
+            }
+            yaml + &lines(None)
+        }
+    }
+}
+
+/// Document with the list at `key` set to `items`
+pub fn set_list(content: &str, key: &str, items: &[String]) -> String {
+    if list(content, key) == items {
+        return content.to_string();
+    }
+    edit_yaml(content, |yaml| {
+        let newline = newline(yaml);
+        replace_entry(yaml, key, |entry| match entry {
+            Some(entry) => edit_list(entry, items, newline),
+            None => {
+                let items: Vec<String> = items.iter().map(|item| scalar(item)).collect();
+                format!("{}: [{}]{}", key, items.join(", "), newline)
+            }
+        })
+    })
+}
+
+/// Document with its `tags` list set to `tags`
+pub fn set_tags(content: &str, tags: &[String]) -> String {
+    set_list(content, "tags", tags)
+}
+
+/// Document with `item` appended to the list at `key`, unless it is there
+pub fn add_to_list(content: &str, key: &str, item: &str) -> String {
+    let mut items = list(content, key);
+    if items.iter().any(|existing| existing == item) {
+        return content.to_string();
+    }
+    items.push(item.to_string());
+    set_list(content, key, &items)
+}
+
+/// Document without `item` in the list at `key`
+pub fn remove_from_list(content: &str, key: &str, item: &str) -> String {
+    let items = list(content, key);
+    if !items.iter().any(|existing| existing == item) {
+        return content.to_string();
+    }
+    let items: Vec<String> = items.into_iter().filter(|existing| existing != item).collect();
+    set_list(content, key, &items)
+}
+
+/// Document with the scalar at `key` set to `value`, quoted like the
+/// current value
+pub fn set_scalar(content: &str, key: &str, value: &str) -> String {
+    edit_yaml(content, |yaml| {
+        let newline = newline(yaml);
+        replace_entry(yaml, key, |entry| match entry {
+            Some(entry) => {
+                let example = (!entry.value.is_empty()).then_some(entry.value.as_str());
+                format!(
+                    "{} {}{}{}",
+                    entry.key,
+                    written_like(value, example),
+                    entry.comment,
+                    newline
+                )
+            }
+            None => format!("{}: {}{}", key, scalar(value), newline),
+        })
+    })
+}
+
+/// Whether the frontmatter of a document is a valid YAML mapping
+fn is_valid(content: &str) -> bool {
+    match split_frontmatter(content).0 {
+        Some(yaml) => matches!(
+            serde_yaml::from_str::<Value>(yaml),
+            Ok(Value::Mapping(_) | Value::Null)
+        ),
+        None => true,
+    }
+}
+
+/// Apply `edit` to the note at `path`, writing it back atomically when it
+/// changed. Refuses to write a frontmatter that would no longer parse.
+/// Returns whether the note was modified.
+pub fn update(path: &Path, edit: impl FnOnce(&str) -> String) -> io::Result<bool> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Cannot read {:?}: {}", path, e)))?;
+    let updated = edit(&content);
+    if updated == content {
+        return Ok(false);
+    }
+    check(path, &content, &updated)?;
+    super::write_note(path, &updated)?;
+    Ok(true)
+}
+
+/// Refuse to turn the note at `path` from `before` into `after` when its
+/// frontmatter parsed before and no longer would
+pub fn check(path: &Path, before: &str, after: &str) -> io::Result<()> {
+    if is_valid(before) && !is_valid(after) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Cannot edit the frontmatter of {:?} without breaking it", path),
+        ));
+    }
+    Ok(())
+}
+
+/// Change the tags of the note at `path` with `update`, writing it back only
+/// when they changed. Returns whether the note was modified.
+pub fn update_tags(path: &Path, edit: impl FnOnce(&mut Vec<String>)) -> io::Result<bool> {
+    update(path, |content| {
+        let mut tags = tags(content);
+        edit(&mut tags);
+        set_tags(content, &tags)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn quotes_scalars_read_back_differently() {
+        for value in ["rust", "project/alpha", "v1.2", "a-b_c"] {
+            assert_eq!(scalar(value), value);
+        }
+        for value in ["", "0x10", "0o17", ".inf", "-.Inf", ".nan", "1e3", "12", "true", "~", "a b", "a,b", "#a"] {
+            let written = scalar(value);
+            assert_ne!(written, value);
+            assert_eq!(serde_yaml::from_str::<Value>(&written).unwrap(), Value::from(value));
+        }
+    }
+
+    #[test]
+    fn edits_flow_lists() {
+        let content = "---\ntitle: A\ntags: [a, 'b c', \"d\"] # topics\n---\nbody #x\n";
+        assert_eq!(
+            add_to_list(content, "tags", "0x10"),
+            "---\ntitle: A\ntags: [a, 'b c', \"d\", \"0x10\"] # topics\n---\nbody #x\n"
+        );
+        assert_eq!(
+            remove_from_list(content, "tags", "b c"),
+            "---\ntitle: A\ntags: [a, \"d\"] # topics\n---\nbody #x\n"
+        );
+        // A renamed item keeps its position and quoting
+        assert_eq!(
+            set_tags(content, &tags(&["a", "it's", "d"])),
+            "---\ntitle: A\ntags: [a, 'it''s', \"d\"] # topics\n---\nbody #x\n"
+        );
+        assert_eq!(
+            set_tags(content, &[]),
+            "---\ntitle: A\ntags: [] # topics\n---\nbody #x\n"
+        );
+        assert_eq!(add_to_list(content, "tags", "a"), content);
+        assert_eq!(remove_from_list(content, "tags", "z"), content);
+    }
+
+    #[test]
+    fn edits_block_lists() {
+        let content = "---\ntags: # topics\n  - a # first\n  # between\n  - \"b\"\nstatus: draft\n---\n";
+        assert_eq!(
+            add_to_list(content, "tags", "c d"),
+            "---\ntags: # topics\n  - a # first\n  # between\n  - \"b\"\n  - \"c d\"\nstatus: draft\n---\n"
+        );
+        assert_eq!(
+            remove_from_list(content, "tags", "a"),
+            "---\ntags: # topics\n  # between\n  - \"b\"\nstatus: draft\n---\n"
+        );
+        assert_eq!(
+            set_tags(content, &tags(&["x", "b"])),
+            "---\ntags: # topics\n  - x # first\n  # between\n  - \"b\"\nstatus: draft\n---\n"
+        );
+        // An emptied list stays a list, keeping its comments
+        let emptied = set_tags(content, &[]);
+        assert_eq!(emptied, "---\ntags: [] # topics\n  # between\nstatus: draft\n---\n");
+        assert!(list(&emptied, "tags").is_empty());
+        assert_eq!(list(&emptied, "status"), ["draft"]);
+    }
+
+    #[test]
+    fn keeps_line_breaks() {
+        let content = "---\r\ntitle: A\r\ntags:\r\n  - a\r\n---\r\nbody\r\n";
+        assert_eq!(
+            add_to_list(content, "tags", "b"),
+            "---\r\ntitle: A\r\ntags:\r\n  - a\r\n  - b\r\n---\r\nbody\r\n"
+        );
+        assert_eq!(
+            set_scalar(content, "status", "done"),
+            "---\r\ntitle: A\r\ntags:\r\n  - a\r\nstatus: done\r\n---\r\nbody\r\n"
+        );
+    }
+
+    #[test]
+    fn sets_scalars() {
+        let content = "---\ntitle: 'Old' # kept\nstatus: draft\n---\n";
+        assert_eq!(
+            set_scalar(content, "title", "It's new"),
+            "---\ntitle: 'It''s new' # kept\nstatus: draft\n---\n"
+        );
+        assert_eq!(
+            set_scalar(content, "status", ".inf"),
+            "---\ntitle: 'Old' # kept\nstatus: \".inf\"\n---\n"
+        );
+        assert_eq!(
+            set_scalar(content, "id", "20260901"),
+            "---\ntitle: 'Old' # kept\nstatus: draft\nid: \"20260901\"\n---\n"
+        );
+    }
+
+    #[test]
+    fn creates_missing_and_empty_blocks() {
+        assert_eq!(add_to_list("Body\n", "tags", "a"), "---\ntags: [a]\n---\n\nBody\n");
+        assert_eq!(set_tags("Body\n", &[]), "Body\n");
+        assert_eq!(add_to_list("---\n---\nBody\n", "tags", "a"), "---\ntags: [a]\n---\nBody\n");
+        assert_eq!(
+            set_scalar("---\n---\nBody\n", "status", "done"),
+            "---\nstatus: done\n---\nBody\n"
+        );
+    }
+
+    #[test]
+    fn refuses_to_break_the_frontmatter() {
+        let path = std::env::temp_dir().join(format!("noteorg-frontmatter-{}.md", std::process::id()));
+        std::fs::write(&path, "---\ntags: [a]\n---\nbody\n").unwrap();
+        assert!(update(&path, |content| content.replace("[a]", "[a")).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "---\ntags: [a]\n---\nbody\n");
+        assert!(update_tags(&path, |tags| tags.push(String::from("b"))).unwrap());
+        assert!(!update_tags(&path, |_| {}).unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "---\ntags: [a, b]\n---\nbody\n");
+
+        // A frontmatter broken already can still be edited
+        std::fs::write(&path, "---\ntags: [a\n---\nbody\n").unwrap();
+        assert!(update(&path, |content| content.replace("body", "text")).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+// Inline `#hashtags` of note bodies, Obsidian style
+//
+// A hashtag starts with `#` at the start of a line or after whitespace and
+// is made of letters, digits, `_`, `-` and `/` (for nested tags such as
+// `#project/alpha`), with at least one character that is not a digit.
+// Fenced code blocks, inline code, headings and URLs are skipped.
+
+use std::ops::Range;
+
+/// A hashtag and its position in the text it was found in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hashtag {
+    /// Tag without its `#`
+    pub name: String,
+    /// Byte range of the tag in the text, `#` included
+    pub span: Range<usize>,
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Whether `name` can be written as a hashtag
+pub fn is_valid(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(is_tag_char)
+        && !name.starts_with('/')
+        && !name.ends_with('/')
+        && !name.contains("//")
+        && !name.chars().all(|c| c.is_ascii_digit() || c == '/')
+}
+
+/// Whether a line opens or closes a fenced code block, returning its marker
+pub(crate) fn fence_marker(line: &str) -> Option<&'static str> {
+    let trimmed = line.trim_start();
+    ["```", "~~~"]
+        .into_iter()
+        .find(|marker| trimmed.starts_with(marker))
+}
+
+fn is_heading(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let text = trimmed.trim_start_matches('#');
+    let level = trimmed.len() - text.len();
+    (1..=6).contains(&level) && (text.is_empty() || text.starts_with([' ', '\t']))
+}
+
+/// Hashtags of a line outside its inline code spans, at `offset` in the text
+fn line_hashtags(line: &str, offset: usize, hashtags: &mut Vec<Hashtag>) {
+    let mut in_code = false;
+    let mut previous: Option<char> = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == '#' && !in_code && previous.is_none_or(char::is_whitespace) {
+            let start = i + 1;
+            let mut end = start;
+            while let Some(&(j, next)) = chars.peek() {
+                if !is_tag_char(next) {
+                    break;
+                }
+                end = j + next.len_utf8();
+                chars.next();
+            }
+            let name = line[start..end].trim_end_matches('/');
+            if is_valid(name) {
+                hashtags.push(Hashtag {
+                    name: name.to_string(),
+                    span: offset + i..offset + start + name.len(),
+                });
+            }
+            previous = line[..end].chars().next_back();
+            continue;
+        }
+        previous = Some(c);
+    }
+}
+
+/// Every hashtag of `text`, in order of appearance
+pub fn find(text: &str) -> Vec<Hashtag> {
+    let mut hashtags = vec![];
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let line_offset = offset;
+        offset += line.len();
+        if let Some(marker) = fence {
+            if line.trim_start().starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = fence_marker(line) {
+            fence = Some(marker);
+            continue;
+        }
+        if is_heading(line) {
+            continue;
+        }
+        line_hashtags(line, line_offset, &mut hashtags);
+    }
+    hashtags
+}
+
+/// Distinct hashtag names of `text`, ignoring case, in order of appearance
+pub fn names(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for hashtag in find(text) {
+        let lowercase = hashtag.name.to_lowercase();
+        if !names.iter().any(|name| name.to_lowercase() == lowercase) {
+            names.push(hashtag.name);
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_hashtags_with_their_spans() {
+        let text = "#start of line, then #rust and #project/alpha.\n";
+        let hashtags = find(text);
+        let spans: Vec<&str> = hashtags.iter().map(|tag| &text[tag.span.clone()]).collect();
+        assert_eq!(spans, ["#start", "#rust", "#project/alpha"]);
+        assert_eq!(hashtags[2].name, "project/alpha");
+        assert_eq!(names("#todo/ #2026 #v2 #a//b #-x #été"), ["todo", "v2", "-x", "été"]);
+        assert_eq!(names("#Rust #rust #RUST"), ["Rust"]);
+    }
+
+    #[test]
+    fn skips_code_headings_and_urls() {
+        let text = "\
+# Heading #notatag
+##nospace #a
+```rust
+let x = 1; // #fenced
+```
+~~~
+#tilde
+~~~
+See `#code` and https://example.com/page#fragment or issue#12, but #kept.
+";
+        // `##nospace` is not a heading, but its tag follows a `#`
+        assert_eq!(names(text), ["a", "kept"]);
+        // An unclosed fence hides the rest of the text
+        assert_eq!(names("#before\n```\n#after\n"), ["before"]);
+    }
+
+    #[test]
+    fn validates_tag_names() {
+        for name in ["rust", "project/alpha", "a-b_c", "v2"] {
+            assert!(is_valid(name), "{}", name);
+        }
+        for name in ["", "2026", "1/2", "/a", "a/", "a//b", "a b", "a#b"] {
+            assert!(!is_valid(name), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// Links of a note to other notes
+//
+// Wikilinks are written `[[target]]`, `[[target|label]]` or
+// `[[target#heading]]`, Obsidian style, embeds (`![[target]]`) included.
+// Markdown links count when they point to a relative `.md` file, such as
+// `[label](../other.md#heading)`. Fenced code blocks and inline code are
+// skipped.
+
+use super::hashtags::fence_marker;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    Wiki,
+    Markdown,
+}
+
+/// A link and its position in the text it was found in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub kind: LinkKind,
+    /// Note linked to, without its heading: a file name, title, alias or path
+    /// for wikilinks, a path relative to the note for Markdown links
+    pub target: String,
+    /// Heading (or `^block` reference) linked to in the target
+    pub heading: Option<String>,
+    /// Text shown instead of the target
+    pub label: Option<String>,
+    /// Byte range of the link in the text
+    pub span: Range<usize>,
+    /// Line of the link, from 1
+    pub line: usize,
+}
+
+/// Target and heading of `target#heading`, `None` for an empty part
+fn split_heading(text: &str) -> (String, Option<String>) {
+    match text.split_once('#') {
+        Some((target, heading)) => (
+            target.trim().to_string(),
+            Some(heading.trim().to_string()).filter(|heading| !heading.is_empty()),
+        ),
+        None => (text.trim().to_string(), None),
+    }
+}
+
+/// Wikilink starting at `[[` in `rest`, with its length
+fn wikilink(rest: &str) -> Option<(usize, String, Option<String>, Option<String>)> {
+    let inner_end = rest[2..].find("]]")? + 2;
+    let inner = &rest[2..inner_end];
+    if inner.contains(['[', ']']) {
+        return None;
+    }
+    let (target, label) = match inner.split_once('|') {
+        // `\|` separates the label inside tables
+        Some((target, label)) => (
+            target.trim_end_matches('\\'),
+            Some(label.trim().to_string()).filter(|label| !label.is_empty()),
+        ),
+        None => (inner, None),
+    };
+    let (target, heading) = split_heading(target);
+    // Links to a heading of the same note are not links between notes
+    if target.is_empty() {
+        return None;
+    }
+    Some((inner_end + 2, target, heading, label))
+}
+
+/// Bytes of `text` with their `%XX` escapes decoded
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Markdown link to a relative `.md` file starting at `[` in `rest`, with
+/// its length
+fn markdown_link(rest: &str) -> Option<(usize, String, Option<String>, Option<String>)> {
+    let label_end = rest.find(']')?;
+    let label = &rest[1..label_end];
+    if label.contains('[') {
+        return None;
+    }
+    let destination = rest[label_end + 1..].strip_prefix('(')?;
+    let close = destination.find(')')?;
+    let destination = destination[..close].trim();
+    // `<path with spaces.md>`, or a path followed by an optional title
+    let path = match destination.strip_prefix('<') {
+        Some(path) => path.strip_suffix('>')?,
+        None => destination.split_whitespace().next()?,
+    };
+    if path.contains("://") || path.starts_with(['/', '#']) || path.starts_with("mailto:") {
+        return None;
+    }
+    let (target, heading) = split_heading(path);
+    let target = percent_decode(&target);
+    if !target.to_lowercase().ends_with(".md") {
+        return None;
+    }
+    let length = label_end + 1 + 1 + close + 1;
+    let label = Some(label.trim().to_string()).filter(|label| !label.is_empty());
+    Some((length, target, heading.map(|h| percent_decode(&h)), label))
+}
+
+/// Links of a line outside its inline code spans, at `offset` in the text
+fn line_links(line: &str, offset: usize, number: usize, links: &mut Vec<Link>) {
+    let mut in_code = false;
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == '[' && !in_code {
+            let found = if rest.starts_with("[[") {
+                wikilink(rest).map(|link| (LinkKind::Wiki, link))
+            } else {
+                markdown_link(rest).map(|link| (LinkKind::Markdown, link))
+            };
+            if let Some((kind, (length, target, heading, label))) = found {
+                links.push(Link {
+                    kind,
+                    target,
+                    heading,
+                    label,
+                    span: offset + i..offset + i + length,
+                    line: number,
+                });
+                i += length;
+                continue;
+            }
+        }
+        i += c.len_utf8();
+    }
+}
+
+/// Every link of `text` to another note, in order of appearance
+pub fn find(text: &str) -> Vec<Link> {
+    let mut links = vec![];
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        let line_offset = offset;
+        offset += line.len();
+        if let Some(marker) = fence {
+            if line.trim_start().starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = fence_marker(line) {
+            fence = Some(marker);
+            continue;
+        }
+        line_links(line, line_offset, i + 1, &mut links);
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Kind, target, heading and label of a link
+    type Parts = (LinkKind, String, Option<String>, Option<String>);
+
+    /// Parts of the links of `text`
+    fn links(text: &str) -> Vec<Parts> {
+        find(text)
+            .into_iter()
+            .map(|link| (link.kind, link.target, link.heading, link.label))
+            .collect()
+    }
+
+    fn wiki(target: &str, heading: Option<&str>, label: Option<&str>) -> Parts {
+        (LinkKind::Wiki, target.into(), heading.map(Into::into), label.map(Into::into))
+    }
+
+    fn markdown(target: &str, heading: Option<&str>, label: Option<&str>) -> Parts {
+        (LinkKind::Markdown, target.into(), heading.map(Into::into), label.map(Into::into))
+    }
+
+    #[test]
+    fn reads_wikilinks() {
+        assert_eq!(
+            links("[[Note]] [[Note|Label]] [[dir/Note#Heading|Label]] ![[Image.png]] [[#Local]] [[]]"),
+            [
+                wiki("Note", None, None),
+                wiki("Note", None, Some("Label")),
+                wiki("dir/Note", Some("Heading"), Some("Label")),
+                wiki("Image.png", None, None),
+            ]
+        );
+        // `\|` separates the label inside tables
+        assert_eq!(
+            links("| [[Note#^block\\|Label]] | [[ Other | ]] |"),
+            [wiki("Note", Some("^block"), Some("Label")), wiki("Other", None, None)]
+        );
+        assert_eq!(links("[[a [b]] c]] [[unclosed"), []);
+    }
+
+    #[test]
+    fn reads_markdown_links() {
+        assert_eq!(
+            links(concat!(
+                "[Label](other.md) [](../up.MD#Some%20Heading \"title\") ",
+                "[spaces](<my note.md#part>) [encoded](my%20note.md) ",
+                "[web](https://x.com/a.md) [abs](/a.md) [anchor](#a) [mail](mailto:a.md) ",
+                "[image](a.png) [[wiki]]"
+            )),
+            [
+                markdown("other.md", None, Some("Label")),
+                markdown("../up.MD", Some("Some Heading"), None),
+                markdown("my note.md", Some("part"), Some("spaces")),
+                markdown("my note.md", None, Some("encoded")),
+                wiki("wiki", None, None),
+            ]
+        );
+        assert_eq!(percent_decode("%41%zz%4"), "A%zz%4");
+    }
+
+    #[test]
+    fn skips_code() {
+        let text = "\
+`[[inline]]` [[kept]]
+```
+[[fenced]]
+~~~
+[[still fenced]]
+```
+~~~md
+[b](b.md)
+~~~
+[c](c.md)
+";
+        assert_eq!(
+            links(text),
+            [wiki("kept", None, None), markdown("c.md", None, Some("c"))]
+        );
+    }
+
+    #[test]
+    fn locates_links() {
+        let text = "First line\né [[Note|x]] and [b](b.md)\n";
+        let found = find(text);
+        assert_eq!(found.iter().map(|link| link.line).collect::<Vec<_>>(), [2, 2]);
+        assert_eq!(&text[found[0].span.clone()], "[[Note|x]]");
+        assert_eq!(&text[found[1].span.clone()], "[b](b.md)");
+    }
+}
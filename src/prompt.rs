@@ -0,0 +1,142 @@
+// Interactive single-line prompts on the terminal
+
+use std::io::{self, IsTerminal, Write};
+
+use crossterm::{
+    cursor, execute,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Color, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType, disable_raw_mode, enable_raw_mode},
+};
+
+/// Whether prompts can be shown, i.e. both stdin and stdout are terminals
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Keeps the terminal in raw mode for as long as it is alive
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        enable_raw_mode()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+fn cancelled() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "Prompt cancelled")
+}
+
+fn is_cancel(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Esc
+        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+fn read_key() -> io::Result<KeyEvent> {
+    loop {
+        if let Event::Key(key) = event::read()?
+            && key.kind != KeyEventKind::Release
+        {
+            return Ok(key);
+        }
+    }
+}
+
+fn clear_line<W: Write>(stdout: &mut W) -> io::Result<()> {
+    execute!(stdout, cursor::MoveToColumn(0), Clear(ClearType::CurrentLine))
+}
+
+/// Ask for a line of text. An empty answer yields `default` when given.
+pub fn prompt_text(label: &str, default: Option<&str>) -> io::Result<String> {
+    let mut stdout = io::stdout();
+    let _raw = RawMode::enable()?;
+    let mut input = String::new();
+
+    loop {
+        clear_line(&mut stdout)?;
+        match default {
+            Some(value) => write!(stdout, "{} [{}]: {}", label, value, input)?,
+            None => write!(stdout, "{}: {}", label, input)?,
+        }
+        stdout.flush()?;
+
+        let key = read_key()?;
+        if is_cancel(&key) {
+            write!(stdout, "\r\n")?;
+            return Err(cancelled());
+        }
+        match key.code {
+            KeyCode::Enter => break,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    write!(stdout, "\r\n")?;
+    if input.is_empty() {
+        Ok(default.unwrap_or_default().to_string())
+    } else {
+        Ok(input)
+    }
+}
+
+/// Ask to pick one of `choices` with the arrow keys (or Tab), starting from
+/// `default` when it is one of them
+pub fn prompt_choice(label: &str, choices: &[String], default: Option<&str>) -> io::Result<String> {
+    if choices.is_empty() {
+        return prompt_text(label, default);
+    }
+    let mut stdout = io::stdout();
+    let _raw = RawMode::enable()?;
+    let mut selected = default
+        .and_then(|value| choices.iter().position(|choice| choice == value))
+        .unwrap_or(0);
+
+    loop {
+        clear_line(&mut stdout)?;
+        write!(stdout, "{} (←→ to choose):", label)?;
+        for (i, choice) in choices.iter().enumerate() {
+            if i == selected {
+                write!(
+                    stdout,
+                    " {}[{}]{}",
+                    SetForegroundColor(Color::Cyan),
+                    choice,
+                    ResetColor
+                )?;
+            } else {
+                write!(stdout, " {}", choice)?;
+            }
+        }
+        stdout.flush()?;
+
+        let key = read_key()?;
+        if is_cancel(&key) {
+            write!(stdout, "\r\n")?;
+            return Err(cancelled());
+        }
+        match key.code {
+            KeyCode::Enter => break,
+            KeyCode::Left | KeyCode::Up | KeyCode::BackTab => {
+                selected = selected.checked_sub(1).unwrap_or(choices.len() - 1);
+            }
+            KeyCode::Right | KeyCode::Down | KeyCode::Tab => {
+                selected = (selected + 1) % choices.len();
+            }
+            _ => {}
+        }
+    }
+
+    write!(stdout, "\r\n")?;
+    Ok(choices[selected].clone())
+}
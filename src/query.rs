@@ -0,0 +1,784 @@
+// Structured query language used by `note search` and `note edit`
+//
+//     tag:rust cat:work/meetings modified:>2026-09-01
+//     title:"weekly sync" OR (created:<7d -tag:draft)
+//     re:/todo|fixme/i NOT cat:archive
+//     status:draft alias:wsync fm.author:alice fm.reviewed:*
+//
+// Terms next to each other are combined with AND. A bare word is a regex
+// matched against the filename, title, tags, category and content, a quoted
+// bare string is matched literally (ignoring case). `-term` and `NOT term`
+// negate a term, `AND`/`OR` and parentheses combine them.
+
+use crate::journal;
+use crate::note::Note;
+use chrono::{NaiveDate, TimeZone};
+use regex::{Regex, RegexBuilder};
+use serde_yaml::Value;
+use std::fmt;
+use std::io;
+
+/// Error pointing at the part of the query it comes from
+#[derive(Debug)]
+pub struct QueryError {
+    pub message: String,
+    /// Byte range of the offending token in the query
+    pub start: usize,
+    pub end: usize,
+    query: String,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset = self.query[..self.start].chars().count();
+        let width = self.query[self.start..self.end].chars().count().max(1);
+        write!(
+            f,
+            "Invalid query: {}\n  {}\n  {}{}",
+            self.message,
+            self.query,
+            " ".repeat(offset),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl From<QueryError> for io::Error {
+    fn from(error: QueryError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// `field:value` or a bare value, `quoted` when written between quotes
+    Term {
+        field: Option<String>,
+        value: String,
+        quoted: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Before,
+    BeforeOrOn,
+    On,
+    OnOrAfter,
+    After,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateField {
+    Created,
+    Modified,
+}
+
+#[derive(Debug)]
+enum Predicate {
+    /// Regex over every searchable field
+    Text(Regex),
+    Tag(String),
+    Category(String),
+    Title(String),
+    Filename(String),
+    Alias(String),
+    Status(String),
+    Id(String),
+    /// Other frontmatter key, with any value when `value` is `None`
+    Field {
+        key: String,
+        value: Option<String>,
+    },
+    Date {
+        field: DateField,
+        comparison: Comparison,
+        date: NaiveDate,
+    },
+}
+
+#[derive(Debug)]
+enum Expr {
+    Predicate(Predicate),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// A parsed query, evaluated against notes with `matches`
+#[derive(Debug)]
+pub struct Query {
+    expr: Option<Expr>,
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn error(&self, message: String, start: usize, end: usize) -> QueryError {
+        QueryError {
+            message,
+            start,
+            end,
+            query: self.input.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    /// Read until the closing quote, handling `\"` escapes
+    fn quoted(&mut self, start: usize) -> Result<String, QueryError> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(value),
+                Some('\\') if self.peek() == Some('"') => {
+                    self.bump();
+                    value.push('"');
+                }
+                Some(c) => value.push(c),
+                None => {
+                    return Err(self.error(
+                        String::from("missing closing quote"),
+                        start,
+                        self.position,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Read `/regex/` with an optional `i` flag, handling `\/` escapes
+    fn regex(&mut self, start: usize) -> Result<String, QueryError> {
+        if self.bump() != Some('/') {
+            return Err(self.error(
+                String::from("expected a regex like re:/pattern/"),
+                start,
+                self.position,
+            ));
+        }
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('/') => break,
+                Some('\\') if self.peek() == Some('/') => {
+                    self.bump();
+                    value.push('/');
+                }
+                Some(c) => value.push(c),
+                None => {
+                    return Err(self.error(
+                        String::from("missing closing / of the regex"),
+                        start,
+                        self.position,
+                    ));
+                }
+            }
+        }
+        if self.peek() == Some('i') {
+            self.bump();
+            value.insert_str(0, "(?i)");
+        }
+        Ok(value)
+    }
+
+    /// Read a word up to whitespace, a quote or a parenthesis. Regex
+    /// syntax is kept whole: escapes, character classes such as `[ ]` and
+    /// groups opened inside the word, as in `todo|(foo bar)`.
+    fn word(&mut self) -> String {
+        let start = self.position;
+        let mut in_class = false;
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '[' if !in_class => in_class = true,
+                ']' if in_class => in_class = false,
+                _ if in_class => {}
+                '(' if self.position > start => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                _ if depth > 0 => {}
+                c if c.is_whitespace() || c == '(' || c == ')' || c == '"' => break,
+                _ => {}
+            }
+            self.bump();
+        }
+        self.input[start..self.position].to_string()
+    }
+
+    fn tokens(mut self) -> Result<Vec<Token>, QueryError> {
+        let mut tokens = vec![];
+        while let Some(c) = self.peek() {
+            let start = self.position;
+            let kind = match c {
+                c if c.is_whitespace() => {
+                    self.bump();
+                    continue;
+                }
+                '(' => {
+                    self.bump();
+                    TokenKind::LParen
+                }
+                ')' => {
+                    self.bump();
+                    TokenKind::RParen
+                }
+                '-' if self.input[start + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| !next.is_whitespace()) =>
+                {
+                    self.bump();
+                    TokenKind::Not
+                }
+                '"' => {
+                    self.bump();
+                    TokenKind::Term {
+                        field: None,
+                        value: self.quoted(start)?,
+                        quoted: true,
+                    }
+                }
+                // The regex may contain spaces and parentheses
+                _ if self.input[start..].starts_with("re:/") => {
+                    self.position += "re:".len();
+                    TokenKind::Term {
+                        field: Some(String::from("re")),
+                        value: self.regex(start)?,
+                        quoted: false,
+                    }
+                }
+                _ => {
+                    let word = self.word();
+                    match word.as_str() {
+                        "AND" => TokenKind::And,
+                        "OR" => TokenKind::Or,
+                        "NOT" => TokenKind::Not,
+                        _ => match word.split_once(':') {
+                            Some((field, "")) if self.peek() == Some('"') => {
+                                let field = field.to_string();
+                                self.bump();
+                                TokenKind::Term {
+                                    field: Some(field),
+                                    value: self.quoted(start)?,
+                                    quoted: true,
+                                }
+                            }
+                            Some((field, value)) if !field.is_empty() => TokenKind::Term {
+                                field: Some(field.to_string()),
+                                value: value.to_string(),
+                                quoted: false,
+                            },
+                            _ => TokenKind::Term {
+                                field: None,
+                                value: word,
+                                quoted: false,
+                            },
+                        },
+                    }
+                }
+            };
+            tokens.push(Token {
+                kind,
+                start,
+                end: self.position,
+            });
+        }
+        Ok(tokens)
+    }
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+    today: NaiveDate,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: String, start: usize, end: usize) -> QueryError {
+        QueryError {
+            message,
+            start,
+            end,
+            query: self.query.to_string(),
+        }
+    }
+
+    fn error_at_end(&self, message: &str) -> QueryError {
+        self.error(message.to_string(), self.query.len(), self.query.len())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.and()?;
+        while self.peek().is_some_and(|t| t.kind == TokenKind::Or) {
+            self.next();
+            let right = self.and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.unary()?;
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::And) => {
+                    self.next();
+                }
+                // Adjacent terms are implicitly combined with AND
+                Some(TokenKind::Not | TokenKind::LParen | TokenKind::Term { .. }) => {}
+                _ => break,
+            }
+            let right = self.unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, QueryError> {
+        if self.peek().is_some_and(|t| t.kind == TokenKind::Not) {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(self.error_at_end("expected a term"));
+        };
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(expr),
+                    _ => Err(self.error(
+                        String::from("unclosed parenthesis"),
+                        token.start,
+                        token.end,
+                    )),
+                }
+            }
+            TokenKind::Term {
+                field,
+                value,
+                quoted,
+            } => Ok(Expr::Predicate(self.predicate(
+                field.as_deref(),
+                &value,
+                quoted,
+                (token.start, token.end),
+            )?)),
+            TokenKind::RParen => Err(self.error(
+                String::from("unexpected closing parenthesis"),
+                token.start,
+                token.end,
+            )),
+            TokenKind::And | TokenKind::Or | TokenKind::Not => Err(self.error(
+                String::from("expected a term before this operator"),
+                token.start,
+                token.end,
+            )),
+        }
+    }
+
+    fn predicate(
+        &self,
+        field: Option<&str>,
+        value: &str,
+        quoted: bool,
+        (start, end): (usize, usize),
+    ) -> Result<Predicate, QueryError> {
+        let error = |message: String| self.error(message, start, end);
+        if value.is_empty() {
+            return Err(error(String::from("missing value after the field name")));
+        }
+        let regex = |pattern: &str, case_insensitive: bool| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(|e| error(format!("invalid regex: {}", e)))
+        };
+
+        match field.map(str::to_lowercase).as_deref() {
+            None if quoted => Ok(Predicate::Text(regex(&regex::escape(value), true)?)),
+            None => Ok(Predicate::Text(regex(value, false)?)),
+            Some("re") => Ok(Predicate::Text(regex(value, false)?)),
+            Some("tag" | "tags") => Ok(Predicate::Tag(value.trim_start_matches('#').to_lowercase())),
+            Some("cat" | "category") => {
+                Ok(Predicate::Category(value.trim_matches('/').to_lowercase()))
+            }
+            Some("title") => Ok(Predicate::Title(value.to_lowercase())),
+            Some("file" | "filename") => Ok(Predicate::Filename(value.to_lowercase())),
+            Some("alias" | "aliases") => Ok(Predicate::Alias(value.to_lowercase())),
+            Some("status") => Ok(Predicate::Status(value.to_lowercase())),
+            Some("id") => Ok(Predicate::Id(value.to_string())),
+            Some(name) if name.starts_with("fm.") && name.len() > "fm.".len() => {
+                Ok(Predicate::Field {
+                    // Frontmatter keys keep their case
+                    key: field.unwrap_or_default()["fm.".len()..].to_string(),
+                    value: (value != "*").then(|| value.to_lowercase()),
+                })
+            }
+            Some(name @ ("created" | "modified")) => {
+                let (comparison, date) = self.date_condition(value).map_err(error)?;
+                Ok(Predicate::Date {
+                    field: if name == "created" {
+                        DateField::Created
+                    } else {
+                        DateField::Modified
+                    },
+                    comparison,
+                    date,
+                })
+            }
+            // Not a field, e.g. the scheme of `https://example.com`: the
+            // whole term is a regex
+            Some(_) if !quoted => Ok(Predicate::Text(regex(&self.query[start..end], false)?)),
+            Some(other) => Err(error(format!(
+                "unknown field {:?}, expected tag, cat, title, file, alias, status, id, created, modified, re or fm.<key>",
+                other
+            ))),
+        }
+    }
+
+    /// Parse `>2026-09-01`, `<=yesterday` or an age such as `<7d`
+    fn date_condition(&self, value: &str) -> Result<(Comparison, NaiveDate), String> {
+        let (comparison, rest) = [
+            (">=", Comparison::OnOrAfter),
+            ("<=", Comparison::BeforeOrOn),
+            (">", Comparison::After),
+            ("<", Comparison::Before),
+            ("=", Comparison::On),
+        ]
+        .iter()
+        .find_map(|(prefix, comparison)| Some((*comparison, value.strip_prefix(prefix)?)))
+        .unwrap_or((Comparison::On, value));
+
+        // An age ("7d" = seven days ago) reverses the comparison: being
+        // younger than 7 days means a date after the threshold
+        let age = Regex::new(r"^\d+[dwmy]$").unwrap();
+        if age.is_match(rest) {
+            let date = journal::parse_date(&format!("-{}", rest), self.today)
+                .map_err(|_| format!("invalid age {:?}", rest))?;
+            let comparison = match comparison {
+                Comparison::Before => Comparison::After,
+                Comparison::BeforeOrOn => Comparison::OnOrAfter,
+                Comparison::After => Comparison::Before,
+                Comparison::OnOrAfter => Comparison::BeforeOrOn,
+                Comparison::On => Comparison::On,
+            };
+            return Ok((comparison, date));
+        }
+
+        journal::parse_date(rest, self.today)
+            .map(|date| (comparison, date))
+            .map_err(|_| format!("invalid date {:?}, expected e.g. 2026-09-01, yesterday or 7d", rest))
+    }
+}
+
+/// Scalars of a frontmatter value, those of its items for a list
+fn field_values(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(string) => vec![string.clone()],
+        Value::Number(number) => vec![number.to_string()],
+        Value::Bool(boolean) => vec![boolean.to_string()],
+        Value::Sequence(items) => items.iter().flat_map(field_values).collect(),
+        _ => vec![],
+    }
+}
+
+impl Predicate {
+    fn matches<Tz: TimeZone>(&self, note: &Note<Tz>) -> bool {
+        let metadata = &note.metadata;
+        match self {
+            Predicate::Text(re) => {
+                re.is_match(&metadata.filename)
+                    || re.is_match(&metadata.title)
+                    || metadata.aliases.iter().any(|alias| re.is_match(alias))
+                    || metadata.tags.iter().any(|tag| re.is_match(&tag.name))
+                    || re.is_match(&metadata.category.join(" "))
+                    || re.is_match(&note.content)
+            }
+            Predicate::Tag(tag) => metadata.tags.iter().any(|t| t.matches(tag)),
+            Predicate::Category(category) => {
+                let path = metadata.category.join("/").to_lowercase();
+                path == *category || path.starts_with(&format!("{}/", category))
+            }
+            Predicate::Title(title) => metadata.title.to_lowercase().contains(title.as_str()),
+            Predicate::Filename(filename) => {
+                metadata.filename.to_lowercase().contains(filename.as_str())
+            }
+            Predicate::Alias(alias) => metadata
+                .aliases
+                .iter()
+                .any(|a| a.to_lowercase().contains(alias.as_str())),
+            Predicate::Status(status) => metadata
+                .status
+                .as_ref()
+                .is_some_and(|s| s.to_lowercase() == *status),
+            Predicate::Id(id) => metadata.id.as_deref() == Some(id.as_str()),
+            Predicate::Field { key, value } => match (metadata.extra.get(key.as_str()), value) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(found), Some(value)) => field_values(found)
+                    .iter()
+                    .any(|found| found.to_lowercase() == *value),
+            },
+            Predicate::Date {
+                field,
+                comparison,
+                date,
+            } => {
+                let note_date = match field {
+                    DateField::Created => metadata.date_created.date_naive(),
+                    DateField::Modified => metadata.date_last_modified.date_naive(),
+                };
+                match comparison {
+                    Comparison::Before => note_date < *date,
+                    Comparison::BeforeOrOn => note_date <= *date,
+                    Comparison::On => note_date == *date,
+                    Comparison::OnOrAfter => note_date >= *date,
+                    Comparison::After => note_date > *date,
+                }
+            }
+        }
+    }
+}
+
+impl Expr {
+    fn matches<Tz: TimeZone>(&self, note: &Note<Tz>) -> bool {
+        match self {
+            Expr::Predicate(predicate) => predicate.matches(note),
+            Expr::Not(expr) => !expr.matches(note),
+            Expr::And(left, right) => left.matches(note) && right.matches(note),
+            Expr::Or(left, right) => left.matches(note) || right.matches(note),
+        }
+    }
+
+    /// Predicates that must match for the expression to match (not under
+    /// NOT), in query order
+    fn positive_predicates<'a>(&'a self, found: &mut Vec<&'a Predicate>) {
+        match self {
+            Expr::Predicate(predicate) => found.push(predicate),
+            Expr::Not(_) => {}
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.positive_predicates(found);
+                right.positive_predicates(found);
+            }
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query, resolving relative dates against `today`
+    pub fn parse(query: &str, today: NaiveDate) -> Result<Self, QueryError> {
+        let tokens = Lexer {
+            input: query,
+            position: 0,
+        }
+        .tokens()?;
+        if tokens.is_empty() {
+            return Ok(Self { expr: None });
+        }
+
+        let mut parser = Parser {
+            query,
+            tokens,
+            position: 0,
+            today,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error(
+                String::from("unexpected token"),
+                token.start,
+                token.end,
+            ));
+        }
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// Whether a note satisfies the query; an empty query matches everything
+    pub fn matches<Tz: TimeZone>(&self, note: &Note<Tz>) -> bool {
+        self.expr.as_ref().is_none_or(|expr| expr.matches(note))
+    }
+
+    /// First regex of the query that is not negated, used to locate matches
+    /// in the content
+    pub fn first_regex(&self) -> Option<&Regex> {
+        let mut predicates = vec![];
+        if let Some(expr) = &self.expr {
+            expr.positive_predicates(&mut predicates);
+        }
+        predicates.into_iter().find_map(|predicate| match predicate {
+            Predicate::Text(re) => Some(re),
+            _ => None,
+        })
+    }
+
+    /// Words of the query that are not negated, used for relevance ranking
+    pub fn keywords(&self) -> String {
+        let mut predicates = vec![];
+        if let Some(expr) = &self.expr {
+            expr.positive_predicates(&mut predicates);
+        }
+        predicates
+            .into_iter()
+            .filter_map(|predicate| match predicate {
+                Predicate::Text(re) => Some(re.as_str().to_string()),
+                Predicate::Tag(value)
+                | Predicate::Category(value)
+                | Predicate::Title(value)
+                | Predicate::Filename(value)
+                | Predicate::Alias(value)
+                | Predicate::Status(value) => Some(value.clone()),
+                Predicate::Field { value, .. } => value.clone(),
+                Predicate::Id(_) | Predicate::Date { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()
+    }
+
+    fn parse(query: &str) -> Query {
+        Query::parse(query, today()).unwrap_or_else(|e| panic!("{:?}: {}", query, e))
+    }
+
+    /// Shape of the expression of a query, e.g. `And(Text(a), Not(Tag(b)))`
+    fn shape(query: &str) -> String {
+        fn shape(expr: &Expr) -> String {
+            match expr {
+                Expr::Predicate(Predicate::Text(re)) => format!("Text({})", re.as_str()),
+                Expr::Predicate(Predicate::Tag(tag)) => format!("Tag({})", tag),
+                Expr::Predicate(predicate) => format!("{:?}", predicate),
+                Expr::Not(expr) => format!("Not({})", shape(expr)),
+                Expr::And(left, right) => format!("And({}, {})", shape(left), shape(right)),
+                Expr::Or(left, right) => format!("Or({}, {})", shape(left), shape(right)),
+            }
+        }
+        parse(query).expr.as_ref().map(shape).unwrap_or_default()
+    }
+
+    #[test]
+    fn combines_terms() {
+        assert_eq!(shape("rust tag:cli"), "And(Text(rust), Tag(cli))");
+        assert_eq!(shape("a OR b c"), "Or(Text(a), And(Text(b), Text(c)))");
+        assert_eq!(shape("(a OR b) -tag:draft"), "And(Or(Text(a), Text(b)), Not(Tag(draft)))");
+        assert_eq!(shape("NOT #x"), "Not(Text(#x))");
+        assert_eq!(shape(r#""a (b)""#), r"Text(a \(b\))");
+        assert_eq!(shape(""), "");
+    }
+
+    #[test]
+    fn unknown_fields_are_text() {
+        assert_eq!(shape("https://x.com"), "Text(https://x.com)");
+        assert_eq!(shape("10:30 tag:a"), "And(Text(10:30), Tag(a))");
+    }
+
+    #[test]
+    fn keeps_regex_syntax_in_words() {
+        assert_eq!(shape("[ ]+-"), "Text([ ]+-)");
+        assert_eq!(shape(r"\x20+-"), r"Text(\x20+-)");
+        assert_eq!(shape("todo|(foo)"), "Text(todo|(foo))");
+        assert_eq!(shape("todo|(foo bar) baz"), "And(Text(todo|(foo bar)), Text(baz))");
+        assert_eq!(shape(r"\(a b"), r"And(Text(\(a), Text(b))");
+        assert_eq!(shape("(tag:a)"), "Tag(a)");
+        assert_eq!(shape("re:/a b/i"), "Text((?i)a b)");
+    }
+
+    #[test]
+    fn reads_date_conditions() {
+        let parser = Parser {
+            query: "",
+            tokens: vec![],
+            position: 0,
+            today: today(),
+        };
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let condition = |value| parser.date_condition(value).unwrap();
+        assert_eq!(condition("2026-09-01"), (Comparison::On, date(2026, 9, 1)));
+        assert_eq!(condition(">=2026-09-01"), (Comparison::OnOrAfter, date(2026, 9, 1)));
+        assert_eq!(condition("<yesterday"), (Comparison::Before, date(2026, 10, 16)));
+        // Younger than 7 days, older than 2 weeks
+        assert_eq!(condition("<7d"), (Comparison::After, date(2026, 10, 10)));
+        assert_eq!(condition("<=7d"), (Comparison::OnOrAfter, date(2026, 10, 10)));
+        assert_eq!(condition(">2w"), (Comparison::Before, date(2026, 10, 3)));
+        assert_eq!(condition("1m"), (Comparison::On, date(2026, 9, 17)));
+        assert!(parser.date_condition("<soon").is_err());
+    }
+
+    #[test]
+    fn filters_notes_by_age() {
+        let note = |date| crate::note::test_note("a.md", &format!("---\ndate: {}\n---\n", date));
+        let recent = parse("created:<7d");
+        assert!(recent.matches(&note("2026-10-15")));
+        assert!(!recent.matches(&note("2026-10-10")));
+        assert!(!recent.matches(&note("2026-09-01")));
+        let old = parse("created:>=1y");
+        assert!(old.matches(&note("2025-10-17")));
+        assert!(!old.matches(&note("2025-10-18")));
+    }
+
+    #[test]
+    fn reports_errors_where_they_are() {
+        for (query, start, end) in [
+            ("(tag:a", 0, 1),
+            ("tag:a )", 6, 7),
+            ("title:\"open", 0, 11),
+            ("created:soon", 0, 12),
+            ("todo(", 0, 5),
+            ("tag:", 0, 4),
+        ] {
+            let error = Query::parse(query, today()).err().unwrap();
+            assert_eq!((error.start, error.end), (start, end), "{}", query);
+        }
+    }
+}
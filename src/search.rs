@@ -1,292 +1,1413 @@
 use regex::Regex;
 use std::io;
 use std::io::Write;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
 use std::path::{Path, PathBuf};
-use crate::{note, traversal};
-use chrono::Utc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::bm25::{self, Ranker};
+use crate::config::{Config, MatchMode, Timezone};
+use crate::editor::EditTarget;
+use crate::fuzzy::{self, TextMatch};
+use crate::index::Index;
+use crate::line_editor::{History, LineEditor};
+use crate::note::{self, Note};
+use crate::query::Query;
+use crate::tags;
+use chrono::{NaiveDate, TimeZone, Utc};
 
 use crossterm::{
+    clipboard::CopyToClipboard,
     cursor, execute,
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    style::{Color, ResetColor, SetForegroundColor},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Attribute, Color, ResetColor, SetAttribute, SetForegroundColor},
     terminal::{
-        Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
+        self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
         enable_raw_mode,
     },
 };
 
+/// A note matched by a search, located at the first match in its content
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    pub path: PathBuf,
+    /// 1-based line of the first match, `None` when only metadata matched
+    pub line: Option<usize>,
+    /// 1-based column (in characters) of the first match
+    pub column: Option<usize>,
+    /// The matching line, or the matched text for metadata matches
+    pub snippet: String,
+    /// BM25 relevance or fuzzy score, 0 when not ranked
+    pub score: f64,
+    /// Positions (in characters) of the matched text in `snippet`
+    pub highlights: Vec<usize>,
+}
+
+impl SearchMatch {
+    fn locate(path: PathBuf, re: Option<&Regex>, content: &str, searchable_text: &str) -> Self {
+        match re.and_then(|re| re.find(content)) {
+            Some(found) => {
+                let line_start = content[..found.start()].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = content[found.start()..]
+                    .find('\n')
+                    .map_or(content.len(), |i| found.start() + i);
+                let line = &content[line_start..line_end];
+                let indent = line.len() - line.trim_start().len();
+                let column = content[line_start..found.start()].chars().count();
+                let skipped = line[..indent].chars().count();
+                let length = found.as_str().chars().count();
+                let snippet = line.trim().to_string();
+                // The match may start in the indentation or end in the
+                // trailing spaces trimmed from the snippet
+                let snippet_length = snippet.chars().count();
+                let start = column.saturating_sub(skipped).min(snippet_length);
+                let end = (column + length).saturating_sub(skipped).min(snippet_length);
+                Self {
+                    path,
+                    line: Some(content[..found.start()].matches('\n').count() + 1),
+                    column: Some(column + 1),
+                    snippet,
+                    score: 0.0,
+                    highlights: (start..end).collect(),
+                }
+            }
+            None => {
+                let snippet = re
+                    .and_then(|re| re.find(searchable_text))
+                    .map(|found| found.as_str().to_string())
+                    .unwrap_or_default();
+                Self {
+                    path,
+                    line: None,
+                    column: None,
+                    highlights: (0..snippet.chars().count()).collect(),
+                    snippet,
+                    score: 0.0,
+                }
+            }
+        }
+    }
+
+    /// Locate the first line of `content` containing one of the (stemmed)
+    /// query terms
+    fn locate_terms(path: PathBuf, content: &str, terms: &[String], score: f64) -> Self {
+        let found = content.lines().enumerate().find(|(_, line)| {
+            bm25::tokenize(line).iter().any(|token| terms.contains(token))
+        });
+        Self {
+            path,
+            line: found.map(|(i, _)| i + 1),
+            column: found.map(|_| 1),
+            snippet: found.map(|(_, line)| line.trim().to_string()).unwrap_or_default(),
+            score,
+            highlights: vec![],
+        }
+    }
+
+    pub fn target(&self) -> EditTarget {
+        EditTarget {
+            path: self.path.clone(),
+            line: self.line,
+        }
+    }
+}
+
+/// Notes searched by the interactive UI, loaded once per session
+struct Corpus {
+    notes: Vec<Note<Timezone>>,
+    ranker: Ranker,
+    /// Date relative query dates are resolved against
+    today: NaiveDate,
+}
+
+impl Corpus {
+    fn load(index: &Index, tz: &Timezone) -> Self {
+        Self {
+            notes: index.notes(tz),
+            ranker: index.ranker(),
+            today: Utc::now().with_timezone(tz).date_naive(),
+        }
+    }
+}
+
+/// Actions applying to the marked notes, or to the selected one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BatchAction {
+    CopyPaths,
+    AddTag,
+    RemoveTag,
+    Move,
+    Delete,
+}
+
+impl BatchAction {
+    /// Label of the prompt asking for the value the action needs
+    fn question(self) -> &'static str {
+        match self {
+            BatchAction::AddTag => "Add tag",
+            BatchAction::RemoveTag => "Remove tag",
+            BatchAction::Move => "Move to category",
+            _ => "",
+        }
+    }
+}
+
+enum Prompt {
+    /// Choosing the action to apply
+    Actions,
+    /// Typing the tag or category an action needs
+    Value(BatchAction, LineEditor),
+    ConfirmDelete,
+}
+
 struct SearchState {
-    input: String,
+    input: LineEditor,
     last_input: String,
+    history: History,
+    mode: MatchMode,
+    last_mode: MatchMode,
     selected_index: usize,
     last_selected: usize,
-    current_results: Vec<PathBuf>,
+    current_results: Vec<SearchMatch>,
+    /// Notes marked with Tab, acted upon together
+    marked: BTreeSet<PathBuf>,
+    /// Question asked in place of the search input
+    prompt: Option<Prompt>,
+    /// Outcome of the last batch action
+    status: Option<String>,
+    /// Query error shown instead of the results
+    error: Option<String>,
+    /// Input and mode of the last search started
+    searched: Option<(String, MatchMode)>,
+    /// Last change of the input or of the mode, searched once typing pauses
+    edited_at: Instant,
+    /// Generation of the search whose results are awaited
+    generation: u64,
+    /// Whether results of the current generation were received yet
+    received: bool,
+    /// Keep the selection when the results arrive, the search being a rerun
+    keep_selection: bool,
+    searching: bool,
+    search_started: Instant,
+    /// Frame of the spinner shown while searching
+    spinner: usize,
+    /// Index of the first result shown, moved to keep the selection visible
+    list_offset: usize,
+    /// Number of results that fit on screen, known once rendered
+    page_size: usize,
+    /// Lines the preview is scrolled by, from its initial position
+    preview_scroll: isize,
+    last_preview_scroll: isize,
     needs_redraw: bool,
 }
 
 impl SearchState {
-    fn new() -> Self {
+    /// Notes a batch action applies to: the marked ones, or else the
+    /// selected one
+    fn batch_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            self.current_results
+                .get(self.selected_index)
+                .map(|result| vec![result.path.clone()])
+                .unwrap_or_default()
+        } else {
+            self.marked.iter().cloned().collect()
+        }
+    }
+
+    /// Editor targets of the batch, at their match when they are in the
+    /// results
+    fn batch_targets(&self) -> Vec<EditTarget> {
+        self.batch_paths()
+            .into_iter()
+            .map(|path| {
+                self.current_results
+                    .iter()
+                    .find(|result| result.path == path)
+                    .map_or_else(|| EditTarget::from(path.clone()), SearchMatch::target)
+            })
+            .collect()
+    }
+
+    fn new(mode: MatchMode) -> Self {
         Self {
-            input: String::new(),
+            input: LineEditor::default(),
             last_input: String::from("__FORCE_DRAW__"),
+            history: History::load(),
+            mode,
+            last_mode: mode,
             selected_index: 0,
             last_selected: usize::MAX,
             current_results: Vec::new(),
+            marked: BTreeSet::new(),
+            prompt: None,
+            status: None,
+            error: None,
+            searched: None,
+            edited_at: Instant::now(),
+            generation: 0,
+            received: false,
+            keep_selection: false,
+            searching: false,
+            search_started: Instant::now(),
+            spinner: 0,
+            list_offset: 0,
+            page_size: 10,
+            preview_scroll: 0,
+            last_preview_scroll: 0,
             needs_redraw: true,
         }
     }
 
     fn has_changes(&self) -> bool {
-        self.input != self.last_input
+        self.input.text() != self.last_input
+            || self.mode != self.last_mode
             || self.selected_index != self.last_selected
+            || self.preview_scroll != self.last_preview_scroll
             || self.needs_redraw
     }
 
     fn mark_rendered(&mut self) {
-        self.last_input = self.input.clone();
+        self.last_input = self.input.text().to_string();
+        self.last_mode = self.mode;
         self.last_selected = self.selected_index;
+        self.last_preview_scroll = self.preview_scroll;
         self.needs_redraw = false;
     }
 }
 
+/// Notes of the configured root matching the query `search_value`
 pub fn search_files(
     search_value: &str,
-    base_path: &Path,
-) -> io::Result<Vec<PathBuf>> {
-    let files = traversal::get_files(base_path)?;
+    config: &Config,
+) -> io::Result<Vec<SearchMatch>> {
+    let tz = config.tz()?;
+    let query = Query::parse(search_value, Utc::now().with_timezone(&tz).date_naive())?;
+    let index = Index::open(&config.root()?, config)?;
+    Ok(search_notes(&query, &index.notes(&tz)))
+}
 
-    let re = Regex::new(search_value).map_err(|e| {
-        io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid regex: {}", e))
-    })?;
+/// Notes matching `query`, located at the first match of its regex terms
+pub fn search_notes<Tz: TimeZone>(query: &Query, notes: &[Note<Tz>]) -> Vec<SearchMatch> {
+    notes.iter().filter_map(|note| match_query(query, note)).collect()
+}
+
+/// A note matching `query`, located at the first match of its regex terms
+fn match_query<Tz: TimeZone>(query: &Query, note: &Note<Tz>) -> Option<SearchMatch> {
+    if !query.matches(note) {
+        return None;
+    }
+    Some(SearchMatch::locate(
+        note.path.clone(),
+        query.first_regex(),
+        &note.content,
+        &searchable_text(note),
+    ))
+}
 
-    let mut matching_files = Vec::new();
+/// A note whose content or metadata matches `re`, located at the first match
+fn match_regex<Tz: TimeZone>(re: &Regex, note: &Note<Tz>) -> Option<SearchMatch> {
+    let searchable_text = searchable_text(note);
+    if !re.is_match(&note.content) && !re.is_match(&searchable_text) {
+        return None;
+    }
+    Some(SearchMatch::locate(note.path.clone(), Some(re), &note.content, &searchable_text))
+}
 
-    for path in files {
-        // Only process .md files
-        let is_markdown = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext == "md")
-            .unwrap_or(false);
+/// Metadata of a note searched by regexes besides its content
+fn searchable_text<Tz: TimeZone>(note: &Note<Tz>) -> String {
+    format!(
+        "{} {} {} {}",
+        note.metadata.filename,
+        note.metadata.title,
+        note.metadata.tag_names().join(" "),
+        note.metadata.category.join(" "),
+    )
+}
 
-        if !is_markdown {
-            continue;
+/// Texts of a note tried by fuzzy and literal matching: its metadata, then
+/// every trimmed line of its content along with the line number
+fn candidates<Tz: TimeZone>(note: &Note<Tz>) -> Vec<(Option<usize>, Cow<'_, str>)> {
+    let metadata = &note.metadata;
+    let mut texts = vec![
+        (None, Cow::from(metadata.title.as_str())),
+        (None, Cow::from(metadata.aliases.join(" "))),
+        (None, Cow::from(metadata.filename.as_str())),
+        (None, Cow::from(metadata.tag_names().join(" "))),
+        (None, Cow::from(metadata.category.join("/"))),
+    ];
+    texts.extend(
+        note.content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (Some(i + 1), Cow::from(line.trim()))),
+    );
+    texts
+}
+
+/// Match every whitespace separated word of `input` against a note with
+/// `matcher`, located at the best match of the first word
+fn match_words<Tz: TimeZone>(
+    input: &str,
+    note: &Note<Tz>,
+    matcher: fn(&str, &str) -> Option<TextMatch>,
+) -> Option<SearchMatch> {
+    let texts = candidates(note);
+    let mut score = 0;
+    let mut located: Option<(usize, Vec<usize>)> = None;
+    for word in input.split_whitespace() {
+        // The first best text wins, metadata before content
+        let (index, found) = texts
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, text))| !text.is_empty())
+            .filter_map(|(index, (_, text))| Some((index, matcher(word, text)?)))
+            .fold(None, |best: Option<(usize, TextMatch)>, (index, found)| match best {
+                Some(best) if best.1.score >= found.score => Some(best),
+                _ => Some((index, found)),
+            })?;
+        score += found.score;
+        match &mut located {
+            None => located = Some((index, found.positions)),
+            Some((first, positions)) if *first == index => positions.extend(found.positions),
+            Some(_) => {}
         }
+    }
+
+    let (index, mut highlights) = located?;
+    highlights.sort_unstable();
+    highlights.dedup();
+    let (line, text) = &texts[index];
+    let indent = line.map_or(0, |line| {
+        let raw = note.content.lines().nth(line - 1).unwrap_or_default();
+        raw[..raw.len() - raw.trim_start().len()].chars().count()
+    });
+    Some(SearchMatch {
+        path: note.path.clone(),
+        line: *line,
+        column: line.map(|_| indent + highlights.first().copied().unwrap_or(0) + 1),
+        snippet: text.to_string(),
+        score: f64::from(score),
+        highlights,
+    })
+}
+
+/// Matching of an input in one mode, applied note by note so that the search
+/// can be interrupted
+struct Matching<'a> {
+    input: &'a str,
+    mode: MatchMode,
+    regex: Option<Regex>,
+    query: Option<Query>,
+    /// BM25 scores of the notes, ranking regex, literal and query matches
+    relevance: HashMap<&'a Path, f64>,
+}
 
-        // Read the note to search in metadata and content
-        match note::read_note(&path, base_path, &Utc) {
-            Ok(note) => {
-                // Concatenate all searchable fields
-                let searchable_text = format!(
-                    "{} {} {} {} {}",
-                    note.metadata.filename,
-                    note.metadata.title,
-                    note.metadata.tags.join(" "),
-                    note.metadata.category.join(" "),
-                    note.content
-                );
-
-                // Check if the regex matches anywhere in the concatenated text
-                if re.is_match(&searchable_text) {
-                    matching_files.push(path);
+impl<'a> Matching<'a> {
+    fn new(input: &'a str, mode: MatchMode, corpus: &'a Corpus) -> Result<Self, String> {
+        let (regex, query, relevance) = match mode {
+            MatchMode::Regex => {
+                let regex = Regex::new(input).map_err(|_| "Invalid regex pattern".to_string())?;
+                (Some(regex), None, relevance(input, corpus))
+            }
+            MatchMode::Fuzzy => (None, None, HashMap::new()),
+            MatchMode::Literal => (None, None, relevance(input, corpus)),
+            MatchMode::Query => {
+                let query = Query::parse(input, corpus.today).map_err(|e| e.to_string())?;
+                let relevance = relevance(&query.keywords(), corpus);
+                (None, Some(query), relevance)
+            }
+        };
+        Ok(Self {
+            input,
+            mode,
+            regex,
+            query,
+            relevance,
+        })
+    }
+
+    fn matches(&self, note: &Note<Timezone>) -> Option<SearchMatch> {
+        let mut found = match self.mode {
+            MatchMode::Regex => match_regex(self.regex.as_ref()?, note)?,
+            MatchMode::Fuzzy => return match_words(self.input, note, fuzzy::fuzzy_match),
+            MatchMode::Literal => match_words(self.input, note, fuzzy::literal_match)?,
+            MatchMode::Query => match_query(self.query.as_ref()?, note)?,
+        };
+        found.score = self.relevance.get(note.path.as_path()).copied().unwrap_or(0.0);
+        Some(found)
+    }
+}
+
+/// BM25 scores of the notes of the corpus for `query`
+fn relevance<'a>(query: &str, corpus: &'a Corpus) -> HashMap<&'a Path, f64> {
+    corpus
+        .ranker
+        .rank(query)
+        .into_iter()
+        .filter_map(|(document, score)| Some((corpus.notes.get(document)?.path.as_path(), score)))
+        .collect()
+}
+
+/// Notes searched per batch of the background search, which checks for
+/// cancellation and reports its matches between batches
+const SEARCH_BATCH: usize = 200;
+
+/// Time without typing before the input is searched
+const DEBOUNCE: Duration = Duration::from_millis(80);
+
+struct SearchJob {
+    generation: u64,
+    input: String,
+    mode: MatchMode,
+    corpus: Arc<Corpus>,
+}
+
+/// Matches found by a search job since its previous update
+struct SearchUpdate {
+    generation: u64,
+    matches: Result<Vec<SearchMatch>, String>,
+    done: bool,
+}
+
+/// Searches running on a worker thread, so that typing is never blocked.
+/// Submitting a job cancels the one in progress.
+struct Searcher {
+    jobs: Sender<SearchJob>,
+    updates: Receiver<SearchUpdate>,
+    /// Generation of the newest job, older jobs stop when they see it changed
+    latest: Arc<AtomicU64>,
+}
+
+impl Searcher {
+    fn spawn() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<SearchJob>();
+        let (update_sender, updates) = mpsc::channel();
+        let latest = Arc::new(AtomicU64::new(0));
+        let worker_latest = Arc::clone(&latest);
+        // The worker stops once the searcher, and so the job sender, is dropped
+        thread::spawn(move || {
+            while let Ok(mut job) = job_receiver.recv() {
+                // Only the newest of the queued jobs is worth running
+                while let Ok(newer) = job_receiver.try_recv() {
+                    job = newer;
                 }
+                run_search(&job, &worker_latest, &update_sender);
             }
-            Err(_) => {
-                // If we can't read the note, skip it silently
-                continue;
+        });
+        Self {
+            jobs,
+            updates,
+            latest,
+        }
+    }
+
+    /// Start searching `input`, returning the generation of the job
+    fn submit(&self, input: &str, mode: MatchMode, corpus: &Arc<Corpus>) -> u64 {
+        let generation = self.cancel();
+        let _ = self.jobs.send(SearchJob {
+            generation,
+            input: input.to_string(),
+            mode,
+            corpus: Arc::clone(corpus),
+        });
+        generation
+    }
+
+    /// Stop the search in progress, returning the new generation
+    fn cancel(&self) -> u64 {
+        self.latest.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Match the notes of a job batch by batch, sending the matches of each
+fn run_search(job: &SearchJob, latest: &AtomicU64, updates: &Sender<SearchUpdate>) {
+    let send = |matches, done| {
+        updates
+            .send(SearchUpdate {
+                generation: job.generation,
+                matches,
+                done,
+            })
+            .is_ok()
+    };
+    let matching = match Matching::new(&job.input, job.mode, &job.corpus) {
+        Ok(matching) => matching,
+        Err(error) => {
+            send(Err(error), true);
+            return;
+        }
+    };
+    for batch in job.corpus.notes.chunks(SEARCH_BATCH) {
+        if latest.load(Ordering::Relaxed) != job.generation {
+            return;
+        }
+        let matches = batch.iter().filter_map(|note| matching.matches(note)).collect();
+        if !send(Ok(matches), false) {
+            return;
+        }
+    }
+    send(Ok(vec![]), true);
+}
+
+/// Notes ranked by BM25 relevance to `query`, best first. `ranker` must
+/// have been built from `notes`, in the same order.
+pub fn rank<Tz: TimeZone>(query: &str, notes: &[Note<Tz>], ranker: &Ranker) -> Vec<SearchMatch> {
+    let terms = bm25::tokenize(query);
+    ranker
+        .rank(query)
+        .into_iter()
+        .filter_map(|(document, score)| {
+            let note = notes.get(document)?;
+            Some(SearchMatch::locate_terms(
+                note.path.clone(),
+                &note.content,
+                &terms,
+                score,
+            ))
+        })
+        .collect()
+}
+
+/// A rectangle of the terminal
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+/// Where the result list and the preview go, side by side on wide
+/// terminals and stacked otherwise
+struct Layout {
+    width: u16,
+    list: Area,
+    preview: Option<Area>,
+    /// The preview is to the right of the list rather than below it
+    side_by_side: bool,
+}
+
+impl Layout {
+    /// Rows taken by the input line and the separator
+    const HEADER: u16 = 2;
+
+    fn new((width, height): (u16, u16)) -> Self {
+        let body = height.saturating_sub(Self::HEADER);
+        if width >= 100 {
+            let list_width = width * 2 / 5;
+            Self {
+                width,
+                list: Area { x: 0, y: Self::HEADER, width: list_width, height: body },
+                preview: Some(Area {
+                    x: list_width + 2,
+                    y: Self::HEADER,
+                    width: width.saturating_sub(list_width + 2),
+                    height: body,
+                }),
+                side_by_side: true,
+            }
+        } else if body >= 12 {
+            let list_height = body / 2;
+            Self {
+                width,
+                list: Area { x: 0, y: Self::HEADER, width, height: list_height },
+                preview: Some(Area {
+                    x: 0,
+                    y: Self::HEADER + list_height + 1,
+                    width,
+                    height: body - list_height - 1,
+                }),
+                side_by_side: false,
+            }
+        } else {
+            Self {
+                width,
+                list: Area { x: 0, y: Self::HEADER, width, height: body },
+                preview: None,
+                side_by_side: false,
             }
         }
     }
+}
 
-    Ok(matching_files)
+fn input_prompt(mode: MatchMode) -> String {
+    format!("Search [{}]: ", mode.name())
+}
+
+/// Label and editor of the first line: the search input, or the question of
+/// a batch action
+fn first_line(state: &SearchState) -> (String, Option<&LineEditor>) {
+    let count = state.batch_paths().len();
+    let notes = plural(count);
+    match &state.prompt {
+        None => (input_prompt(state.mode), Some(&state.input)),
+        Some(Prompt::Actions) => (
+            format!(
+                "{} {}: [o]pen, [y] copy paths, [t] add tag, [u] remove tag, [m]ove, [d]elete, Esc to cancel",
+                count, notes
+            ),
+            None,
+        ),
+        Some(Prompt::Value(action, input)) => {
+            (format!("{} ({} {}): ", action.question(), count, notes), Some(input))
+        }
+        Some(Prompt::ConfirmDelete) => (format!("Delete {} {}? [y/N] ", count, notes), None),
+    }
 }
 
-fn render_search_input<W: Write>(stdout: &mut W, input: &str) -> io::Result<()> {
+fn render_search_input<W: Write>(stdout: &mut W, state: &SearchState) -> io::Result<()> {
+    let (label, input) = first_line(state);
     execute!(stdout, cursor::MoveTo(0, 0))?;
-    write!(stdout, "Search: {}", input)?;
+    write!(stdout, "{}{}", label, input.map_or("", LineEditor::text))?;
+    Ok(())
+}
+
+/// Write `text` with the characters at `highlights` colored, keeping at most
+/// `width` characters and scrolling horizontally when the first highlight
+/// would not be visible
+fn render_highlighted<W: Write>(
+    stdout: &mut W,
+    text: &str,
+    highlights: &[usize],
+    color: Color,
+    width: usize,
+) -> io::Result<()> {
+    let start = match highlights.first() {
+        Some(&first) if first + 1 > width * 2 / 3 => first - width / 3,
+        _ => 0,
+    };
+    for (i, c) in text.chars().enumerate().skip(start).take(width) {
+        if highlights.binary_search(&i).is_ok() {
+            write!(stdout, "{}{}{}", SetForegroundColor(Color::Yellow), c, SetForegroundColor(color))?;
+        } else {
+            write!(stdout, "{}", c)?;
+        }
+    }
     Ok(())
 }
 
-fn render_separator<W: Write>(stdout: &mut W) -> io::Result<()> {
+fn render_separators<W: Write>(stdout: &mut W, layout: &Layout) -> io::Result<()> {
+    let help = "─ ↑↓ PgUp/PgDn Home/End select · Enter edit · Tab mark · Ctrl-X actions · Ctrl-R mode · Shift+↑↓/PgUp/PgDn scroll preview · Alt+↑↓ history ";
     execute!(stdout, cursor::MoveTo(0, 1))?;
-    write!(stdout, "{}", "─".repeat(50))?;
+    write!(
+        stdout,
+        "{}",
+        help.chars()
+            .chain(std::iter::repeat('─'))
+            .take(layout.width.into())
+            .collect::<String>()
+    )?;
+    if let Some(preview) = layout.preview {
+        if layout.side_by_side {
+            for y in preview.y..preview.y + preview.height {
+                execute!(stdout, cursor::MoveTo(layout.list.width, y))?;
+                write!(stdout, "│")?;
+            }
+        } else {
+            execute!(stdout, cursor::MoveTo(0, preview.y - 1))?;
+            write!(stdout, "{}", "─".repeat(layout.width.into()))?;
+        }
+    }
     Ok(())
 }
 
-fn render_results<W: Write>(
-    stdout: &mut W,
-    state: &mut SearchState,
-    base_path: &Path,
-) -> io::Result<()> {
-    if state.input.is_empty() {
-        execute!(stdout, cursor::MoveTo(0, 2))?;
-        write!(stdout, "Start typing to search...")?;
+/// Recompute the results when the input or the mode changed
+fn update_results(state: &mut SearchState, update: SearchUpdate) {
+    if update.generation != state.generation {
+        return;
+    }
+    if !state.received {
+        // Replace the results of the previous search with the first matches
+        state.received = true;
+        state.error = None;
         state.current_results.clear();
-        state.selected_index = 0;
-        return Ok(());
+        if !state.keep_selection {
+            state.selected_index = 0;
+            state.list_offset = 0;
+            state.preview_scroll = 0;
+        }
     }
+    match update.matches {
+        Ok(matches) => {
+            state.current_results.extend(matches);
+            // Stable, so that equal scores keep the order of the notes
+            state.current_results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+        Err(error) => state.error = Some(error),
+    }
+    state.selected_index = state
+        .selected_index
+        .min(state.current_results.len().saturating_sub(1));
+    state.searching &= !update.done;
+    state.needs_redraw = true;
+}
 
-    match search_files(&state.input, base_path) {
-        Ok(files) => {
-            let input_changed = state.input != state.last_input;
-            if input_changed {
-                state.current_results = files;
-                state.selected_index = 0;
-            }
+/// Start searching the input with the current mode. `keep_selection` is for
+/// reruns of the same search on reloaded notes.
+fn start_search(
+    state: &mut SearchState,
+    searcher: &Searcher,
+    corpus: &Arc<Corpus>,
+    keep_selection: bool,
+) {
+    let input = state.input.text().to_string();
+    state.keep_selection = keep_selection;
+    state.received = false;
+    if input.is_empty() {
+        state.generation = searcher.cancel();
+        update_results(
+            state,
+            SearchUpdate {
+                generation: state.generation,
+                matches: Ok(vec![]),
+                done: true,
+            },
+        );
+    } else {
+        state.generation = searcher.submit(&input, state.mode, corpus);
+        state.searching = true;
+        state.search_started = Instant::now();
+    }
+    state.searched = Some((input, state.mode));
+}
 
-            if state.current_results.is_empty() {
-                execute!(stdout, cursor::MoveTo(0, 2))?;
-                write!(stdout, "No matches")?;
-            } else {
-                execute!(stdout, cursor::MoveTo(0, 2))?;
-                write!(
-                    stdout,
-                    "Found {} matches (↑↓ to select, Enter to edit):",
-                    state.current_results.len()
-                )?;
-
-                for (i, file) in state.current_results.iter().enumerate().take(10) {
-                    let filename = file
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown");
-
-                    execute!(stdout, cursor::MoveTo(0, 3 + i as u16))?;
-
-                    if i == state.selected_index {
-                        write!(
-                            stdout,
-                            "{}▶ {}. {}{}",
-                            SetForegroundColor(Color::Cyan),
-                            i + 1,
-                            filename,
-                            ResetColor
-                        )?;
-                    } else {
-                        write!(stdout, "  {}. {}", i + 1, filename)?;
-                    }
-                }
+/// Whether the input or the mode changed since the last search, and typing
+/// paused long enough to search them
+fn search_due(state: &SearchState) -> bool {
+    let current = (state.input.text(), state.mode);
+    state
+        .searched
+        .as_ref()
+        .is_none_or(|(input, mode)| (input.as_str(), *mode) != current)
+        && state.edited_at.elapsed() >= DEBOUNCE
+}
+
+/// Number of result rows of the list and the first result to show so that
+/// the selection stays visible
+fn scroll_results(state: &mut SearchState, area: Area) {
+    state.page_size = usize::from(area.height.saturating_sub(1)).max(1);
+    if state.selected_index < state.list_offset {
+        state.list_offset = state.selected_index;
+    } else if state.selected_index >= state.list_offset + state.page_size {
+        state.list_offset = state.selected_index + 1 - state.page_size;
+    }
+    // Fill the list when it was shortened, e.g. after a resize
+    state.list_offset = state
+        .list_offset
+        .min(state.current_results.len().saturating_sub(state.page_size));
+}
+
+/// Frames of the spinner shown while searching
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+fn render_results<W: Write>(stdout: &mut W, state: &SearchState, area: Area) -> io::Result<()> {
+    let width = usize::from(area.width);
+    let header = if state.input.text().is_empty() {
+        String::from("Start typing to search...")
+    } else if let Some(error) = &state.error {
+        error.clone()
+    } else if state.current_results.is_empty() && state.searching {
+        String::from("No matches yet")
+    } else if state.current_results.is_empty() {
+        String::from("No matches")
+    } else {
+        format!(
+            "{} of {} matches",
+            state.selected_index + 1,
+            state.current_results.len()
+        )
+    };
+    let spinner = state
+        .searching
+        .then(|| format!("{} searching", SPINNER[state.spinner % SPINNER.len()]));
+    let header = [
+        Some(header),
+        spinner,
+        (!state.marked.is_empty()).then(|| format!("{} marked", state.marked.len())),
+        state.status.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ");
+    for (i, line) in header.lines().enumerate().take(area.height.into()) {
+        execute!(stdout, cursor::MoveTo(area.x, area.y + i as u16))?;
+        write!(stdout, "{}", line.chars().take(width).collect::<String>())?;
+    }
+
+    let visible = state
+        .current_results
+        .iter()
+        .enumerate()
+        .skip(state.list_offset)
+        .take(state.page_size);
+    for (row, (i, result)) in visible.enumerate() {
+        let filename = result
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown");
+        let color = if i == state.selected_index {
+            Color::Cyan
+        } else {
+            Color::Reset
+        };
+        let marker = if i == state.selected_index { "▶" } else { " " };
+        let mark = if state.marked.contains(&result.path) { "●" } else { " " };
+        let prefix = format!("{}{} {}. ", marker, mark, i + 1);
+        let score = match state.mode {
+            MatchMode::Fuzzy => format!(" ({:.0})", result.score),
+            _ => format!(" ({:.2})", result.score),
+        };
+        let mut available = width.saturating_sub(prefix.chars().count() + score.len());
+
+        execute!(stdout, cursor::MoveTo(area.x, area.y + 1 + row as u16))?;
+        write!(stdout, "{}{}", SetForegroundColor(color), prefix)?;
+        if result.snippet == filename {
+            render_highlighted(stdout, filename, &result.highlights, color, available)?;
+        } else {
+            write!(stdout, "{}", filename.chars().take(available).collect::<String>())?;
+            available = available.saturating_sub(filename.chars().count() + 2);
+            if !result.snippet.is_empty() && available > 0 {
+                write!(stdout, "  ")?;
+                render_highlighted(stdout, &result.snippet, &result.highlights, color, available)?;
             }
         }
-        Err(_) => {
-            execute!(stdout, cursor::MoveTo(0, 2))?;
-            write!(stdout, "Invalid regex pattern")?;
-            state.current_results.clear();
+        write!(stdout, "{}{}", score, ResetColor)?;
+    }
+    Ok(())
+}
+
+/// Positions of the text to highlight in a line of the previewed note
+fn preview_highlights(line: &str, state: &SearchState, today: NaiveDate) -> Vec<usize> {
+    let mut highlights = match state.mode {
+        // Fuzzy matches are only meaningful on the matched line
+        MatchMode::Fuzzy => vec![],
+        MatchMode::Literal => state
+            .input
+            .text()
+            .split_whitespace()
+            .flat_map(|word| {
+                let mut positions = vec![];
+                let mut offset = 0;
+                let chars: Vec<char> = line.chars().collect();
+                while offset < chars.len() {
+                    let rest: String = chars[offset..].iter().collect();
+                    let Some(found) = fuzzy::literal_match(word, &rest) else {
+                        break;
+                    };
+                    positions.extend(found.positions.iter().map(|p| p + offset));
+                    offset += found.positions.last().map_or(chars.len(), |last| last + 1);
+                }
+                positions
+            })
+            .collect(),
+        MatchMode::Regex => Regex::new(state.input.text())
+            .map(|re| regex_highlights(line, &re))
+            .unwrap_or_default(),
+        MatchMode::Query => Query::parse(state.input.text(), today)
+            .ok()
+            .and_then(|query| Some(regex_highlights(line, query.first_regex()?)))
+            .unwrap_or_default(),
+    };
+    highlights.sort_unstable();
+    highlights.dedup();
+    highlights
+}
+
+/// Positions of the matches of `re` in `line`
+fn regex_highlights(line: &str, re: &Regex) -> Vec<usize> {
+    re.find_iter(line)
+        .flat_map(|found| {
+            let start = line[..found.start()].chars().count();
+            start..start + found.as_str().chars().count()
+        })
+        .collect()
+}
+
+/// Draw the selected note, returning the scrolling actually applied once
+/// kept within the note
+fn render_preview<W: Write>(
+    stdout: &mut W,
+    state: &SearchState,
+    corpus: &Corpus,
+    area: Area,
+) -> io::Result<isize> {
+    let Some(result) = state.current_results.get(state.selected_index) else {
+        return Ok(0);
+    };
+    let Some(note) = corpus.notes.iter().find(|note| note.path == result.path) else {
+        return Ok(0);
+    };
+    let width = usize::from(area.width);
+    let metadata = &note.metadata;
+    let mut y = area.y;
+    let bottom = area.y + area.height;
+
+    let title = if metadata.title.is_empty() {
+        &metadata.filename
+    } else {
+        &metadata.title
+    };
+    let details = [
+        (
+            "Tags",
+            metadata
+                .tags
+                .iter()
+                .map(|tag| format!("#{}", tag.name))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        ("Category", metadata.category.join("/")),
+        ("Created", metadata.date_created.format("%Y-%m-%d %H:%M").to_string()),
+        ("Modified", metadata.date_last_modified.format("%Y-%m-%d %H:%M").to_string()),
+    ];
+    execute!(stdout, cursor::MoveTo(area.x, y))?;
+    write!(
+        stdout,
+        "{}{}{}",
+        SetAttribute(Attribute::Bold),
+        title.chars().take(width).collect::<String>(),
+        SetAttribute(Attribute::Reset)
+    )?;
+    y += 1;
+    for (label, value) in details {
+        if y >= bottom {
+            return Ok(0);
         }
+        execute!(stdout, cursor::MoveTo(area.x, y))?;
+        let line = format!("{}: {}", label, value);
+        write!(
+            stdout,
+            "{}{}{}",
+            SetForegroundColor(Color::DarkGrey),
+            line.chars().take(width).collect::<String>(),
+            ResetColor
+        )?;
+        y += 1;
     }
+    y += 1;
 
-    Ok(())
+    // Body lines, starting a little above the match, moved by the scrolling
+    let lines: Vec<&str> = note.content.lines().collect();
+    let body_start = lines.len() - note.body().lines().count();
+    let matched = result.line.map(|line| line - 1);
+    let initial = matched.map_or(body_start, |line| line.saturating_sub(3).max(body_start));
+    let last_page = lines
+        .len()
+        .saturating_sub(usize::from(bottom.saturating_sub(y)))
+        .max(body_start);
+    let first = initial
+        .saturating_add_signed(state.preview_scroll)
+        .clamp(body_start, last_page.max(initial));
+
+    for (number, line) in lines.iter().enumerate().skip(first) {
+        if y >= bottom {
+            break;
+        }
+        execute!(stdout, cursor::MoveTo(area.x, y))?;
+        let highlights = if Some(number) == matched {
+            let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+            result.highlights.iter().map(|p| p + indent).collect()
+        } else {
+            preview_highlights(line, state, corpus.today)
+        };
+        render_highlighted(stdout, line, &highlights, Color::Reset, width)?;
+        y += 1;
+    }
+    Ok(first as isize - initial as isize)
 }
 
 fn render_search_ui<W: Write>(
     stdout: &mut W,
     state: &mut SearchState,
-    base_path: &Path,
+    corpus: &Corpus,
 ) -> io::Result<()> {
+    let layout = Layout::new(terminal::size()?);
+    scroll_results(state, layout.list);
     execute!(stdout, cursor::MoveTo(0, 0), Clear(ClearType::All))?;
-    render_search_input(stdout, &state.input)?;
-    render_separator(stdout)?;
-    render_results(stdout, state, base_path)?;
+    render_search_input(stdout, state)?;
+    render_separators(stdout, &layout)?;
+    render_results(stdout, state, layout.list)?;
+    if let Some(preview) = layout.preview {
+        state.preview_scroll = render_preview(stdout, state, corpus, preview)?;
+    }
+    // Leave the terminal cursor in the input
+    let (label, input) = first_line(state);
+    let column = label.chars().count() + input.map_or(0, LineEditor::cursor_column);
+    execute!(
+        stdout,
+        cursor::MoveTo(
+            u16::try_from(column).unwrap_or(u16::MAX).min(layout.width.saturating_sub(1)),
+            0
+        )
+    )?;
     stdout.flush()?;
     Ok(())
 }
 
+/// Lines scrolled in the preview by Shift+PageUp/PageDown
+const PREVIEW_PAGE: isize = 10;
+
 enum SearchAction {
     Continue,
     Exit,
-    OpenEditor(PathBuf),
-    MoveUp,
-    MoveDown,
-    AddChar(char),
-    DeleteChar,
+    OpenEditor(Vec<EditTarget>),
+    /// Mark or unmark the selected result, then select the next one
+    ToggleMark(isize),
+    ShowPrompt(Prompt),
+    ClosePrompt,
+    EditPrompt(KeyEvent),
+    RunBatch(BatchAction, Option<String>),
+    /// Move the selection by a number of results
+    Move(isize),
+    /// Move the selection by a number of pages
+    MovePage(isize),
+    MoveFirst,
+    MoveLast,
+    ScrollPreview(isize),
+    ToggleMode,
+    HistoryPrevious,
+    HistoryNext,
+    /// Any other key, given to the line editor
+    EditInput(KeyEvent),
     ClearAndExit,
 }
 
+/// Keys of the batch action prompts
+fn handle_prompt_key(key: KeyEvent, prompt: &Prompt, state: &SearchState) -> SearchAction {
+    if key.code == KeyCode::Esc
+        || key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+    {
+        return SearchAction::ClosePrompt;
+    }
+    match prompt {
+        Prompt::Actions => match key.code {
+            KeyCode::Char('o') => SearchAction::OpenEditor(state.batch_targets()),
+            KeyCode::Char('y') => SearchAction::RunBatch(BatchAction::CopyPaths, None),
+            KeyCode::Char('t') => {
+                SearchAction::ShowPrompt(Prompt::Value(BatchAction::AddTag, LineEditor::default()))
+            }
+            KeyCode::Char('u') => SearchAction::ShowPrompt(Prompt::Value(
+                BatchAction::RemoveTag,
+                LineEditor::default(),
+            )),
+            KeyCode::Char('m') => {
+                SearchAction::ShowPrompt(Prompt::Value(BatchAction::Move, LineEditor::default()))
+            }
+            KeyCode::Char('d') => SearchAction::ShowPrompt(Prompt::ConfirmDelete),
+            _ => SearchAction::Continue,
+        },
+        Prompt::Value(action, input) => match key.code {
+            KeyCode::Enter => SearchAction::RunBatch(*action, Some(input.text().to_string())),
+            _ => SearchAction::EditPrompt(key),
+        },
+        Prompt::ConfirmDelete => match key.code {
+            KeyCode::Char('y' | 'Y') => SearchAction::RunBatch(BatchAction::Delete, None),
+            _ => SearchAction::ClosePrompt,
+        },
+    }
+}
+
 fn handle_keyboard_event(key: KeyEvent, state: &SearchState) -> SearchAction {
+    if let Some(prompt) = &state.prompt {
+        return handle_prompt_key(key, prompt, state);
+    }
     match key.code {
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             SearchAction::Exit
         }
-        KeyCode::Up => SearchAction::MoveUp,
-        KeyCode::Down => SearchAction::MoveDown,
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            SearchAction::ToggleMode
+        }
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            SearchAction::Move(-1)
+        }
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            SearchAction::Move(1)
+        }
+        KeyCode::Up if key.modifiers.contains(KeyModifiers::ALT) => SearchAction::HistoryPrevious,
+        KeyCode::Down if key.modifiers.contains(KeyModifiers::ALT) => SearchAction::HistoryNext,
+        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            SearchAction::ScrollPreview(-1)
+        }
+        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            SearchAction::ScrollPreview(1)
+        }
+        KeyCode::PageUp if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            SearchAction::ScrollPreview(-PREVIEW_PAGE)
+        }
+        KeyCode::PageDown if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            SearchAction::ScrollPreview(PREVIEW_PAGE)
+        }
+        KeyCode::Up => SearchAction::Move(-1),
+        KeyCode::Down => SearchAction::Move(1),
+        KeyCode::PageUp => SearchAction::MovePage(-1),
+        KeyCode::PageDown => SearchAction::MovePage(1),
+        // Home and End move the cursor of the input until it is at the
+        // start or end, then select the first or last result
+        KeyCode::Home
+            if key.modifiers.contains(KeyModifiers::CONTROL) || state.input.at_start() =>
+        {
+            SearchAction::MoveFirst
+        }
+        KeyCode::End if key.modifiers.contains(KeyModifiers::CONTROL) || state.input.at_end() => {
+            SearchAction::MoveLast
+        }
         KeyCode::Enter => {
-            if !state.current_results.is_empty() && state.selected_index < state.current_results.len() {
-                SearchAction::OpenEditor(state.current_results[state.selected_index].clone())
+            let targets = state.batch_targets();
+            if targets.is_empty() {
+                SearchAction::Continue
             } else {
+                SearchAction::OpenEditor(targets)
+            }
+        }
+        KeyCode::Tab => SearchAction::ToggleMark(1),
+        KeyCode::BackTab => SearchAction::ToggleMark(-1),
+        KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if state.batch_paths().is_empty() {
                 SearchAction::Continue
+            } else {
+                SearchAction::ShowPrompt(Prompt::Actions)
             }
         }
-        KeyCode::Char(c) => SearchAction::AddChar(c),
-        KeyCode::Backspace => SearchAction::DeleteChar,
         KeyCode::Esc => SearchAction::ClearAndExit,
-        _ => SearchAction::Continue,
+        _ => SearchAction::EditInput(key),
     }
 }
 
-fn apply_action(action: SearchAction, state: &mut SearchState) -> Option<PathBuf> {
+/// Select the result at `index`, kept within the results
+fn select(state: &mut SearchState, index: usize) {
+    let index = index.min(state.current_results.len().saturating_sub(1));
+    if index != state.selected_index {
+        state.selected_index = index;
+        state.preview_scroll = 0;
+    }
+}
+
+fn apply_action(action: SearchAction, state: &mut SearchState) {
     match action {
-        SearchAction::MoveUp => {
-            if state.selected_index > 0 {
-                state.selected_index -= 1;
+        SearchAction::Move(offset) => {
+            select(state, state.selected_index.saturating_add_signed(offset));
+        }
+        SearchAction::MovePage(pages) => {
+            let offset = pages.saturating_mul(state.page_size as isize);
+            select(state, state.selected_index.saturating_add_signed(offset));
+        }
+        SearchAction::MoveFirst => {
+            select(state, 0);
+        }
+        SearchAction::MoveLast => {
+            select(state, usize::MAX);
+        }
+        SearchAction::ScrollPreview(lines) => {
+            state.preview_scroll = state.preview_scroll.saturating_add(lines);
+        }
+        SearchAction::ToggleMode => {
+            state.mode = state.mode.next();
+        }
+        SearchAction::ToggleMark(step) => {
+            if let Some(result) = state.current_results.get(state.selected_index) {
+                if !state.marked.remove(&result.path) {
+                    state.marked.insert(result.path.clone());
+                }
+                state.needs_redraw = true;
+                select(state, state.selected_index.saturating_add_signed(step));
+            }
+        }
+        SearchAction::ShowPrompt(prompt) => {
+            state.prompt = Some(prompt);
+            state.needs_redraw = true;
+        }
+        SearchAction::ClosePrompt => {
+            state.prompt = None;
+            state.needs_redraw = true;
+        }
+        SearchAction::EditPrompt(key) => {
+            if let Some(Prompt::Value(_, input)) = &mut state.prompt {
+                input.handle_key(key);
+                state.needs_redraw = true;
             }
-            None
         }
-        SearchAction::MoveDown => {
-            if state.selected_index < state.current_results.len().saturating_sub(1) {
-                state.selected_index += 1;
+        SearchAction::HistoryPrevious => {
+            if let Some(entry) = state.history.older(state.input.text()) {
+                state.input.set_text(entry);
             }
-            None
         }
-        SearchAction::AddChar(c) => {
-            state.input.push(c);
-            None
+        SearchAction::HistoryNext => {
+            if let Some(entry) = state.history.newer() {
+                state.input.set_text(entry);
+            }
         }
-        SearchAction::DeleteChar => {
-            state.input.pop();
-            None
+        SearchAction::EditInput(key) if state.input.handle_key(key) => {
+            state.history.reset();
+            // The cursor may have moved without changing the text
+            state.needs_redraw = true;
         }
-        SearchAction::OpenEditor(path) => Some(path),
-        _ => None,
+        _ => {}
     }
 }
 
-pub fn show_search_results_realtime(base_path: &Path) -> io::Result<()> {
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "note" } else { "notes" }
+}
+
+/// Apply a batch action to the marked notes, or to the selected one, and
+/// describe what was done
+fn run_batch(
+    action: BatchAction,
+    value: Option<&str>,
+    state: &mut SearchState,
+    config: &Config,
+) -> io::Result<String> {
+    let paths = state.batch_paths();
+    let message = match action {
+        BatchAction::CopyPaths => {
+            let text = paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text))?;
+            format!("Copied {} {}", paths.len(), if paths.len() == 1 { "path" } else { "paths" })
+        }
+        BatchAction::AddTag => {
+            let tag = tags::parse_name(value.unwrap_or_default())?;
+            let mut changed = 0;
+            for path in &paths {
+                changed += tags::add_tags(path, std::slice::from_ref(&tag))? as usize;
+            }
+            format!("Added #{} to {} {}", tag, changed, plural(changed))
+        }
+        BatchAction::RemoveTag => {
+            let tag = tags::parse_name(value.unwrap_or_default())?;
+            let mut changed = 0;
+            for path in &paths {
+                changed += tags::remove_tags(path, std::slice::from_ref(&tag))? as usize;
+            }
+            format!("Removed #{} from {} {}", tag, changed, plural(changed))
+        }
+        BatchAction::Move => {
+            let category = value.unwrap_or_default().trim();
+            let dir = note::category_dir(&config.root()?, category)?;
+            for path in &paths {
+                note::move_note(path, &dir)?;
+            }
+            state.marked.clear();
+            format!("Moved {} {} to {}", paths.len(), plural(paths.len()), category)
+        }
+        BatchAction::Delete => {
+            for path in &paths {
+                fs::remove_file(path)
+                    .map_err(|e| io::Error::new(e.kind(), format!("Cannot delete {:?}: {}", path, e)))?;
+            }
+            state.marked.clear();
+            format!("Deleted {} {}", paths.len(), plural(paths.len()))
+        }
+    };
+    Ok(message)
+}
+
+pub fn show_search_results_realtime(
+    query: &str,
+    mode: Option<MatchMode>,
+    config: &Config,
+) -> io::Result<()> {
+    let tz = config.tz()?;
+    let mut index = Index::open(&config.root()?, config)?;
+    let mut corpus = Arc::new(Corpus::load(&index, &tz));
+    let searcher = Searcher::spawn();
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
 
-    let mut state = SearchState::new();
+    let mut state = SearchState::new(mode.unwrap_or(config.search_mode));
+    state.input.set_text(query.to_string());
+    start_search(&mut state, &searcher, &corpus, false);
     let mut should_exit = false;
 
     while !should_exit {
+        while let Ok(update) = searcher.updates.try_recv() {
+            update_results(&mut state, update);
+        }
+        if state.input.text() != state.last_input || state.mode != state.last_mode {
+            state.edited_at = Instant::now();
+        }
+        if search_due(&state) {
+            start_search(&mut state, &searcher, &corpus, false);
+        }
+        if state.searching {
+            let frame = (state.search_started.elapsed().as_millis() / 100) as usize;
+            state.needs_redraw |= frame != state.spinner;
+            state.spinner = frame;
+        }
         if state.has_changes() {
-            render_search_ui(&mut stdout, &mut state, base_path)?;
+            render_search_ui(&mut stdout, &mut state, &corpus)?;
             state.mark_rendered();
         }
 
-        if event::poll(std::time::Duration::from_millis(50))?
-            && let Event::Key(key) = event::read()?
-        {
-            let action = handle_keyboard_event(key, &state);
-
-            match action {
+        if !event::poll(Duration::from_millis(20))? {
+            continue;
+        }
+        match event::read()? {
+            Event::Resize(_, _) => state.needs_redraw = true,
+            // Terminals reporting key releases send them besides the presses
+            Event::Key(key) if key.kind == KeyEventKind::Release => {}
+            Event::Key(key) => match handle_keyboard_event(key, &state) {
                 SearchAction::Exit | SearchAction::ClearAndExit => {
+                    // History is a convenience, failing to save it is not an error
+                    let _ = state.history.add(state.input.text());
                     should_exit = true;
                 }
-                SearchAction::OpenEditor(path) => {
+                SearchAction::OpenEditor(targets) => {
+                    let _ = state.history.add(state.input.text());
+                    state.prompt = None;
                     disable_raw_mode()?;
                     execute!(stdout, LeaveAlternateScreen)?;
 
-                    crate::editor::launch_editor(&[path])?;
+                    // An editor failing to start is reported, not fatal
+                    let launched = crate::editor::launch_editor(&targets, config.editor.as_deref());
+                    if let Err(e) = launched {
+                        state.status = Some(e.to_string());
+                    }
+
+                    // The note was probably edited
+                    if index.refresh(config)? {
+                        corpus = Arc::new(Corpus::load(&index, &tz));
+                        start_search(&mut state, &searcher, &corpus, true);
+                    }
 
                     enable_raw_mode()?;
                     execute!(stdout, EnterAlternateScreen)?;
                     state.needs_redraw = true;
                 }
-                _ => {
+                SearchAction::RunBatch(action, value) => {
+                    state.prompt = None;
+                    state.status = Some(run_batch(action, value.as_deref(), &mut state, config)
+                        .unwrap_or_else(|e| e.to_string()));
+                    // Notes were changed, moved or deleted
+                    if index.refresh(config)? {
+                        corpus = Arc::new(Corpus::load(&index, &tz));
+                        start_search(&mut state, &searcher, &corpus, true);
+                    }
+                    state.needs_redraw = true;
+                }
+                action => {
+                    if state.status.take().is_some() {
+                        state.needs_redraw = true;
+                    }
                     apply_action(action, &mut state);
                 }
-            }
+            },
+            _ => {}
         }
     }
 
@@ -294,3 +1415,159 @@ pub fn show_search_results_realtime(base_path: &Path) -> io::Result<()> {
     execute!(stdout, LeaveAlternateScreen)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locate(pattern: &str, content: &str) -> SearchMatch {
+        let re = Regex::new(pattern).unwrap();
+        SearchMatch::locate(PathBuf::from("note.md"), Some(&re), content, "")
+    }
+
+    #[test]
+    fn highlights_the_match_in_the_snippet() {
+        let found = locate("todo", "# Title\n  - [ ] todo\n");
+        assert_eq!((found.line, found.column), (Some(2), Some(9)));
+        assert_eq!(found.snippet, "- [ ] todo");
+        assert_eq!(found.highlights, vec![6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn matches_regexes_in_content_and_metadata() {
+        let note = note::test_note(
+            "work/sync.md",
+            "---\ntitle: Weekly Sync\ntags: [meeting]\n---\n\nAgenda: (budget)\n",
+        );
+        let re = |pattern: &str| Regex::new(pattern).unwrap();
+        let found = match_regex(&re(r"\(budget\)"), &note).unwrap();
+        assert_eq!((found.line, found.snippet.as_str()), (Some(6), "Agenda: (budget)"));
+        let found = match_regex(&re("meet.ng"), &note).unwrap();
+        assert_eq!((found.line, found.snippet.as_str()), (Some(3), "tags: [meeting]"));
+        let found = match_regex(&re(r"sync\.md"), &note).unwrap();
+        assert_eq!((found.line, found.snippet.as_str()), (None, "sync.md"));
+        assert!(match_regex(&re("^Budget"), &note).is_none());
+    }
+
+    #[test]
+    fn home_and_end_select_once_the_cursor_is_at_the_edge() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut state = SearchState::new(MatchMode::Regex);
+        assert!(matches!(
+            handle_keyboard_event(key(KeyCode::Home), &state),
+            SearchAction::MoveFirst
+        ));
+        assert!(matches!(
+            handle_keyboard_event(key(KeyCode::End), &state),
+            SearchAction::MoveLast
+        ));
+
+        state.input.set_text(String::from("todo"));
+        assert!(matches!(
+            handle_keyboard_event(key(KeyCode::Home), &state),
+            SearchAction::EditInput(_)
+        ));
+        assert!(matches!(
+            handle_keyboard_event(key(KeyCode::End), &state),
+            SearchAction::MoveLast
+        ));
+        let control_home = KeyEvent::new(KeyCode::Home, KeyModifiers::CONTROL);
+        assert!(matches!(
+            handle_keyboard_event(control_home, &state),
+            SearchAction::MoveFirst
+        ));
+    }
+
+    #[test]
+    fn clamps_matches_in_trimmed_spaces() {
+        let found = locate(r"\x20+-", "    - [ ] todo\n");
+        assert_eq!(found.column, Some(1));
+        assert_eq!(found.snippet, "- [ ] todo");
+        assert_eq!(found.highlights, vec![0]);
+
+        let found = locate(r"todo\s+$", "todo   \n");
+        assert_eq!(found.highlights, vec![0, 1, 2, 3]);
+
+        let found = locate(r"^\s+$", "a\n   \nb");
+        assert_eq!(found.snippet, "");
+        assert!(found.highlights.is_empty());
+    }
+
+    #[test]
+    fn marks_notes_and_edits_their_tags() {
+        let dir = std::env::temp_dir().join(format!("noteorg-{}-batch", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths: Vec<PathBuf> = ["a.md", "b.md", "c.md"].iter().map(|name| dir.join(name)).collect();
+        let mut state = SearchState::new(MatchMode::Regex);
+        for path in &paths {
+            fs::write(path, "---\ntags: [Draft]\n---\n").unwrap();
+            state.current_results.push(SearchMatch::locate(path.clone(), None, "", ""));
+        }
+        // Without marks, actions apply to the selected note
+        assert_eq!(state.batch_paths(), [paths[0].clone()]);
+        apply_action(SearchAction::ToggleMark(1), &mut state);
+        apply_action(SearchAction::ToggleMark(1), &mut state);
+        assert_eq!(state.selected_index, 2);
+        apply_action(SearchAction::ToggleMark(-1), &mut state);
+        // Marking again unmarks
+        apply_action(SearchAction::ToggleMark(0), &mut state);
+        assert_eq!(state.batch_paths(), [paths[0].clone(), paths[2].clone()]);
+
+        let config = Config::default();
+        let mut batch = |action, value| run_batch(action, Some(value), &mut state, &config);
+        assert_eq!(batch(BatchAction::AddTag, "#ideas").unwrap(), "Added #ideas to 2 notes");
+        assert_eq!(batch(BatchAction::AddTag, "IDEAS").unwrap(), "Added #IDEAS to 0 notes");
+        assert_eq!(batch(BatchAction::RemoveTag, "draft").unwrap(), "Removed #draft from 2 notes");
+        for invalid in ["", "two words", "a,b"] {
+            let error = batch(BatchAction::AddTag, invalid).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+        let contents: Vec<String> = paths.iter().map(|path| fs::read_to_string(path).unwrap()).collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(contents[0], "---\ntags: [ideas]\n---\n");
+        assert_eq!(contents[1], "---\ntags: [Draft]\n---\n");
+        assert_eq!(contents[2], contents[0]);
+    }
+
+    #[test]
+    fn discards_results_of_stale_searches() {
+        let tz = Timezone::Fixed(chrono::FixedOffset::east_opt(0).unwrap());
+        let notes: Vec<Note<Timezone>> = [("a.md", "alpha"), ("b.md", "beta")]
+            .iter()
+            .map(|(path, content)| note::test_note(path, content).with_timezone(&tz))
+            .collect();
+        let terms: Vec<_> = notes.iter().map(bm25::DocumentTerms::from_note).collect();
+        let corpus = Arc::new(Corpus {
+            notes,
+            ranker: Ranker::new(&terms),
+            today: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+        });
+        let searcher = Searcher::spawn();
+        let mut state = SearchState::new(MatchMode::Regex);
+        state.input.set_text(String::from("alpha"));
+        start_search(&mut state, &searcher, &corpus, false);
+        let stale = state.generation;
+        state.input.set_text(String::from("beta"));
+        start_search(&mut state, &searcher, &corpus, false);
+        assert!(state.generation > stale);
+
+        // Late matches of the first search change nothing
+        let late = SearchMatch::locate(PathBuf::from("a.md"), None, "alpha", "");
+        update_results(
+            &mut state,
+            SearchUpdate {
+                generation: stale,
+                matches: Ok(vec![late]),
+                done: true,
+            },
+        );
+        assert!(state.current_results.is_empty() && state.searching);
+
+        while state.searching {
+            let update = searcher.updates.recv_timeout(Duration::from_secs(5)).unwrap();
+            update_results(&mut state, update);
+        }
+        let paths: Vec<&Path> = state.current_results.iter().map(|result| result.path.as_path()).collect();
+        assert_eq!(paths, [Path::new("b.md")]);
+    }
+}
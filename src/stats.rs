@@ -0,0 +1,383 @@
+// Vault statistics
+
+use crate::config::Timezone;
+use crate::links::LinkGraph;
+use crate::note::Note;
+use crate::traversal::CategoryTree;
+use chrono::DateTime;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// Number of entries shown in the "largest" and "stalest" rankings
+const TOP_COUNT: usize = 5;
+/// Number of periods shown in the human-readable activity histograms
+const RECENT_WEEKS: usize = 8;
+const RECENT_MONTHS: usize = 12;
+/// Width of the longest histogram bar
+const BAR_WIDTH: usize = 30;
+
+#[derive(Serialize, Debug)]
+pub struct CategoryStats {
+    pub name: String,
+    /// Notes directly inside this category
+    pub notes: usize,
+    /// Notes inside this category and all its subcategories
+    pub total: usize,
+    pub children: Vec<CategoryStats>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct NoteSummary {
+    pub title: String,
+    pub path: PathBuf,
+    pub words: usize,
+    pub characters: usize,
+    pub last_modified: DateTime<Timezone>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct Activity {
+    pub per_week: BTreeMap<String, usize>,
+    pub per_month: BTreeMap<String, usize>,
+}
+
+/// A link no note fits
+#[derive(Serialize, Debug, Clone)]
+pub struct BrokenLink {
+    pub path: PathBuf,
+    pub line: usize,
+    pub target: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Statistics {
+    pub total_notes: usize,
+    pub total_words: usize,
+    pub total_characters: usize,
+    pub categories: CategoryStats,
+    /// Tags sorted by decreasing frequency
+    pub tags: Vec<(String, usize)>,
+    /// Frontmatter `status` values sorted by decreasing frequency
+    pub statuses: Vec<(String, usize)>,
+    pub created: Activity,
+    pub modified: Activity,
+    pub largest: Vec<NoteSummary>,
+    pub stalest: Vec<NoteSummary>,
+    /// Notes without tags sitting directly at the vault root
+    pub untagged: Vec<NoteSummary>,
+    /// Links of the notes to other notes
+    pub links: usize,
+    pub broken_links: Vec<BrokenLink>,
+    /// Notes neither linking to nor linked from another note
+    pub unlinked: Vec<NoteSummary>,
+}
+
+fn summarize(note: &Note<Timezone>) -> NoteSummary {
+    let body = note.body();
+    NoteSummary {
+        title: note.metadata.title.clone(),
+        path: note.path.clone(),
+        words: body.split_whitespace().count(),
+        characters: body.chars().count(),
+        last_modified: note.metadata.date_last_modified,
+    }
+}
+
+fn category_stats(
+    tree: &CategoryTree,
+    prefix: &mut Vec<String>,
+    counts: &HashMap<Vec<String>, usize>,
+) -> CategoryStats {
+    let children: Vec<CategoryStats> = tree
+        .children
+        .iter()
+        .map(|child| {
+            prefix.push(child.name.clone());
+            let stats = category_stats(child, prefix, counts);
+            prefix.pop();
+            stats
+        })
+        .filter(|child| child.total > 0)
+        .collect();
+    let notes = counts.get(prefix.as_slice()).copied().unwrap_or(0);
+    let total = notes + children.iter().map(|child| child.total).sum::<usize>();
+    CategoryStats {
+        name: tree.name.clone(),
+        notes,
+        total,
+        children,
+    }
+}
+
+fn record(activity: &mut Activity, date: &DateTime<Timezone>) {
+    *activity
+        .per_week
+        .entry(date.format("%G-W%V").to_string())
+        .or_default() += 1;
+    *activity
+        .per_month
+        .entry(date.format("%Y-%m").to_string())
+        .or_default() += 1;
+}
+
+/// Distinct values with their number of occurrences, most frequent first
+fn by_frequency<'a>(values: impl Iterator<Item = &'a String>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value.clone()).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Tags with the number of notes carrying them, ignoring case, most
+/// frequent first. Each tag is named as first written.
+fn tag_frequency(notes: &[Note<Timezone>]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+    for tag in notes.iter().flat_map(|note| &note.metadata.tags) {
+        counts
+            .entry(tag.name.to_lowercase())
+            .or_insert_with(|| (tag.name.clone(), 0))
+            .1 += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_values().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Statistics of `notes`, their links resolved in `graph`, which may span
+/// more notes
+pub fn compute(
+    notes: &[Note<Timezone>],
+    graph: &LinkGraph,
+    tree: &CategoryTree,
+) -> Statistics {
+    let mut summaries: Vec<NoteSummary> = notes.iter().map(summarize).collect();
+
+    // Categories are resolved against the tree rather than the raw path so
+    // that they match what `traversal` reports.
+    let categories: Vec<Vec<String>> = notes
+        .iter()
+        .map(|note| {
+            tree.get_categories(&note.path)
+                .unwrap_or_else(|_| note.metadata.category.clone())
+        })
+        .collect();
+    let mut category_counts: HashMap<Vec<String>, usize> = HashMap::new();
+    for categories in &categories {
+        *category_counts.entry(categories.clone()).or_default() += 1;
+    }
+
+    let tags = tag_frequency(notes);
+    let statuses = by_frequency(notes.iter().filter_map(|note| note.metadata.status.as_ref()));
+
+    let mut created = Activity::default();
+    let mut modified = Activity::default();
+    for note in notes {
+        record(&mut created, &note.metadata.date_created);
+        record(&mut modified, &note.metadata.date_last_modified);
+    }
+
+    let untagged = notes
+        .iter()
+        .zip(&categories)
+        .filter(|(note, categories)| note.metadata.tags.is_empty() && categories.is_empty())
+        .map(|(note, _)| summarize(note))
+        .collect();
+
+    let mut links = 0;
+    let mut broken_links = vec![];
+    let mut unlinked = vec![];
+    for note in notes {
+        let Some(index) = graph.index_of(&note.path) else {
+            continue;
+        };
+        for edge in graph.outgoing(index) {
+            links += 1;
+            if edge.target.is_none() {
+                broken_links.push(BrokenLink {
+                    path: note.path.clone(),
+                    line: edge.link.line,
+                    target: edge.link.target.clone(),
+                });
+            }
+        }
+        if graph.is_isolated(index) {
+            unlinked.push(summarize(note));
+        }
+    }
+
+    let total_words = summaries.iter().map(|s| s.words).sum();
+    let total_characters = summaries.iter().map(|s| s.characters).sum();
+
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.characters));
+    let largest = summaries.iter().take(TOP_COUNT).cloned().collect();
+    summaries.sort_by_key(|s| s.last_modified);
+    summaries.truncate(TOP_COUNT);
+
+    Statistics {
+        total_notes: notes.len(),
+        total_words,
+        total_characters,
+        categories: category_stats(tree, &mut vec![], &category_counts),
+        tags,
+        statuses,
+        created,
+        modified,
+        largest,
+        stalest: summaries,
+        untagged,
+        links,
+        broken_links,
+        unlinked,
+    }
+}
+
+fn labelled_tree(stats: &CategoryStats, is_root: bool) -> CategoryTree {
+    CategoryTree {
+        name: if is_root {
+            stats.name.clone()
+        } else {
+            format!("{} ({})", stats.name, stats.total)
+        },
+        children: stats
+            .children
+            .iter()
+            .map(|child| labelled_tree(child, false))
+            .collect(),
+    }
+}
+
+fn print_histogram<'a>(entries: impl Iterator<Item = (&'a str, usize)> + Clone) {
+    let max = entries.clone().map(|(_, count)| count).max().unwrap_or(0);
+    let width = entries.clone().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+    for (label, count) in entries {
+        let bar = (count * BAR_WIDTH).div_ceil(max.max(1));
+        println!("  {:<width$} {:>5} {}", label, count, "█".repeat(bar));
+    }
+}
+
+fn print_recent(title: &str, periods: &BTreeMap<String, usize>, count: usize) {
+    println!("\n{}:", title);
+    if periods.is_empty() {
+        println!("  none");
+        return;
+    }
+    let skip = periods.len().saturating_sub(count);
+    print_histogram(periods.iter().skip(skip).map(|(k, v)| (k.as_str(), *v)));
+}
+
+fn print_summaries(title: &str, summaries: &[NoteSummary]) {
+    println!("\n{}:", title);
+    if summaries.is_empty() {
+        println!("  none");
+    }
+    for summary in summaries {
+        println!(
+            "  {} ({} words, modified {}) {}",
+            summary.title,
+            summary.words,
+            summary.last_modified.format("%Y-%m-%d"),
+            summary.path.display()
+        );
+    }
+}
+
+pub fn print_human(stats: &Statistics) {
+    println!(
+        "Notes: {} ({} words, {} characters)",
+        stats.total_notes, stats.total_words, stats.total_characters
+    );
+
+    println!("\nCategories:");
+    labelled_tree(&stats.categories, true).display();
+
+    println!("\nTags:");
+    if stats.tags.is_empty() {
+        println!("  none");
+    } else {
+        print_histogram(stats.tags.iter().map(|(tag, count)| (tag.as_str(), *count)));
+    }
+
+    println!("\nStatuses:");
+    if stats.statuses.is_empty() {
+        println!("  none");
+    } else {
+        print_histogram(stats.statuses.iter().map(|(status, count)| (status.as_str(), *count)));
+    }
+
+    print_recent("Created per week", &stats.created.per_week, RECENT_WEEKS);
+    print_recent("Created per month", &stats.created.per_month, RECENT_MONTHS);
+    print_recent("Modified per week", &stats.modified.per_week, RECENT_WEEKS);
+    print_recent("Modified per month", &stats.modified.per_month, RECENT_MONTHS);
+
+    print_summaries("Largest notes", &stats.largest);
+    print_summaries("Stalest notes", &stats.stalest);
+    print_summaries("Untagged notes (no tags, no category)", &stats.untagged);
+
+    println!("\nLinks: {} ({} broken)", stats.links, stats.broken_links.len());
+    for link in &stats.broken_links {
+        println!("  {}:{} {}", link.path.display(), link.line, link.target);
+    }
+    print_summaries("Unlinked notes (no links in or out)", &stats.unlinked);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traversal::parse_categories_tree_from_path;
+    use std::fs;
+
+    #[test]
+    fn computes_statistics() {
+        let root = std::env::temp_dir().join(format!("noteorg-{}-stats", std::process::id()));
+        fs::create_dir_all(root.join("work/old")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        let tz = Timezone::Fixed(chrono::FixedOffset::east_opt(0).unwrap());
+        let notes: Vec<Note<Timezone>> = [
+            ("inbox.md", "no tags, [[plan]]"),
+            ("loose.md", "---\ndate: 2026-02-10\n---\nalone"),
+            ("work/plan.md", "---\ntags: [Rust, draft]\nstatus: done\n---\none two three"),
+            ("work/old/log.md", "---\ntags: [rust]\nstatus: done\n---\n#Draft [[missing]]"),
+        ]
+        .iter()
+        .map(|(path, content)| {
+            // Categories are read from the directories of the notes
+            let path = root.join(path);
+            fs::write(&path, content).unwrap();
+            crate::note::test_note(&path.to_string_lossy(), content).with_timezone(&tz)
+        })
+        .collect();
+        let tree = parse_categories_tree_from_path(&root).unwrap();
+        let graph = LinkGraph::build(&notes);
+        let stats = compute(&notes, &graph, &tree);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(stats.total_notes, 4);
+        assert_eq!(stats.total_words, 3 + 1 + 3 + 2);
+        assert_eq!((stats.categories.notes, stats.categories.total), (2, 4));
+        // Empty categories are left out
+        let work = &stats.categories.children;
+        assert_eq!(work.len(), 1);
+        assert_eq!((work[0].name.as_str(), work[0].notes, work[0].total), ("work", 1, 2));
+        assert_eq!(work[0].children[0].name, "old");
+
+        assert_eq!(stats.tags, [(String::from("Rust"), 2), (String::from("draft"), 2)]);
+        assert_eq!(stats.statuses, [(String::from("done"), 2)]);
+        assert_eq!(stats.created.per_month.get("2026-01"), Some(&3));
+        assert_eq!(stats.created.per_month.get("2026-02"), Some(&1));
+        assert_eq!(stats.created.per_week.get("2026-W07"), Some(&1));
+        assert_eq!(stats.largest[0].title, "log");
+
+        let titles = |summaries: &[NoteSummary]| {
+            summaries.iter().map(|summary| summary.title.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(titles(&stats.untagged), ["inbox", "loose"]);
+        assert_eq!(stats.links, 2);
+        assert_eq!(stats.broken_links.len(), 1);
+        assert_eq!((stats.broken_links[0].line, stats.broken_links[0].target.as_str()), (5, "missing"));
+        assert_eq!(titles(&stats.unlinked), ["loose", "log"]);
+    }
+}
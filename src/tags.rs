@@ -0,0 +1,390 @@
+// Vault wide tag management: counting, renaming and merging tags
+//
+// Renaming rewrites the `tags` key of the frontmatter and the inline
+// hashtags of the body. Nested tags follow their parent: renaming `project`
+// to `work` turns `project/alpha` into `work/alpha`.
+
+use crate::note::frontmatter;
+use crate::note::{Note, hashtags, split_frontmatter};
+use crate::traversal::CategoryTree;
+use chrono::TimeZone;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Number of notes carrying a tag or one nested below it, per tag path
+#[derive(Debug, Default)]
+pub struct TagTree {
+    /// Notes tagged with this tag or a nested one
+    pub count: usize,
+    /// Nested tags, by lowercase name
+    pub children: BTreeMap<String, (String, TagTree)>,
+}
+
+impl TagTree {
+    pub fn from_notes<Tz: TimeZone>(notes: &[Note<Tz>]) -> Self {
+        let mut tree = TagTree::default();
+        for note in notes {
+            // Each tag path counts a note once, however many of its tags
+            // are nested below it
+            let mut seen: Vec<String> = vec![];
+            for tag in &note.metadata.tags {
+                let segments: Vec<&str> = tag.name.split('/').collect();
+                for depth in 1..=segments.len() {
+                    let path = segments[..depth].join("/").to_lowercase();
+                    if seen.contains(&path) {
+                        continue;
+                    }
+                    seen.push(path);
+                    tree.node(&segments[..depth]).count += 1;
+                }
+            }
+        }
+        tree.count = notes.len();
+        tree
+    }
+
+    fn node(&mut self, segments: &[&str]) -> &mut TagTree {
+        let mut node = self;
+        for segment in segments {
+            node = &mut node
+                .children
+                .entry(segment.to_lowercase())
+                .or_insert_with(|| (segment.to_string(), TagTree::default()))
+                .1;
+        }
+        node
+    }
+
+    /// Tree labelled with the tag names and their counts, for display
+    pub fn labelled(&self, name: &str) -> CategoryTree {
+        CategoryTree {
+            name: name.to_string(),
+            children: self
+                .children
+                .values()
+                .map(|(name, child)| child.labelled(&format!("#{} ({})", name, child.count)))
+                .collect(),
+        }
+    }
+}
+
+/// Tag name written by the user, without its `#`
+pub fn parse_name(name: &str) -> io::Result<String> {
+    let name = name.trim().trim_start_matches('#');
+    if !hashtags::is_valid(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Invalid tag {:?}: use letters, digits, `_`, `-` and `/` for nested tags",
+                name
+            ),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Whether the frontmatter tag `tag` is `name`, ignoring case and a
+/// leading `#`, as when matching tags
+pub fn same_tag(tag: &str, name: &str) -> bool {
+    tag.trim_start_matches('#').to_lowercase() == name.trim_start_matches('#').to_lowercase()
+}
+
+/// Add the tags `names` the note at `path` does not have yet to its
+/// frontmatter. Returns whether the note was modified.
+pub fn add_tags(path: &Path, names: &[String]) -> io::Result<bool> {
+    frontmatter::update_tags(path, |tags| {
+        for name in names {
+            if !tags.iter().any(|tag| same_tag(tag, name)) {
+                tags.push(name.clone());
+            }
+        }
+    })
+}
+
+/// Remove the tags `names` from the frontmatter of the note at `path`.
+/// Returns whether the note was modified.
+pub fn remove_tags(path: &Path, names: &[String]) -> io::Result<bool> {
+    frontmatter::update_tags(path, |tags| {
+        tags.retain(|tag| !names.iter().any(|name| same_tag(tag, name)))
+    })
+}
+
+/// `name` with the longest of the tags `from` it is or is nested below
+/// replaced by `to`, `None` when it is none of them
+pub fn renamed(name: &str, from: &[String], to: &str) -> Option<String> {
+    let lowercase = name.to_lowercase();
+    from.iter()
+        .filter(|tag| {
+            let tag = tag.to_lowercase();
+            lowercase == tag || lowercase.starts_with(&format!("{}/", tag))
+        })
+        .max_by_key(|tag| tag.chars().count())
+        .map(|tag| {
+            let nested: String = name.chars().skip(tag.chars().count()).collect();
+            format!("{}{}", to, nested)
+        })
+}
+
+/// Document with the tags `from` renamed to `to` in its frontmatter and its
+/// hashtags, `None` when it has none of them
+pub fn rename_in_content(content: &str, from: &[String], to: &str) -> Option<String> {
+    let mut changed = false;
+
+    let tags = frontmatter::tags(content);
+    let mut content = content.to_string();
+    if tags
+        .iter()
+        .any(|tag| renamed(tag.trim_start_matches('#'), from, to).is_some())
+    {
+        let mut updated: Vec<String> = vec![];
+        for tag in tags {
+            let tag = renamed(tag.trim_start_matches('#'), from, to).unwrap_or(tag);
+            // The new name may already be there
+            if !updated.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+                updated.push(tag);
+            }
+        }
+        content = frontmatter::set_tags(&content, &updated);
+        changed = true;
+    }
+
+    let body_offset = content.len() - split_frontmatter(&content).1.len();
+    let hashtags = hashtags::find(&content[body_offset..]);
+    // From the end, so that the spans of the earlier hashtags stay valid
+    for hashtag in hashtags.iter().rev() {
+        if let Some(name) = renamed(&hashtag.name, from, to) {
+            let span = body_offset + hashtag.span.start..body_offset + hashtag.span.end;
+            content.replace_range(span, &format!("#{}", name));
+            changed = true;
+        }
+    }
+
+    changed.then_some(content)
+}
+
+/// A note whose tags are rewritten, with its current and new content
+pub struct Rewrite {
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+/// Rewrites renaming the tags `from` to `to` in the notes carrying them
+pub fn plan_rename<Tz: TimeZone>(
+    notes: &[Note<Tz>],
+    from: &[String],
+    to: &str,
+) -> io::Result<Vec<Rewrite>> {
+    let mut rewrites = vec![];
+    for note in notes {
+        if !note
+            .metadata
+            .tags
+            .iter()
+            .any(|tag| from.iter().any(|name| tag.matches(name)))
+        {
+            continue;
+        }
+        // The index may lag behind the file, rewrite what is on disk
+        let before = fs::read_to_string(&note.path).map_err(|e| {
+            io::Error::new(e.kind(), format!("Cannot read {:?}: {}", note.path, e))
+        })?;
+        if let Some(after) = rename_in_content(&before, from, to) {
+            // Checked before writing any note, so that none is renamed when
+            // one would break
+            frontmatter::check(&note.path, &before, &after)?;
+            rewrites.push(Rewrite {
+                path: note.path.clone(),
+                before,
+                after,
+            });
+        }
+    }
+    Ok(rewrites)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Line changes turning `before` into `after`, from their longest common
+/// subsequence
+fn diff_lines<'a>(before: &[&'a str], after: &[&'a str]) -> Vec<(Change, &'a str)> {
+    // Lines shared at both ends are left out of the quadratic part
+    let prefix = before
+        .iter()
+        .zip(after)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &before[prefix..before.len() - suffix];
+    let new = &after[prefix..after.len() - suffix];
+
+    // lengths[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<(Change, &str)> =
+        before[..prefix].iter().map(|line| (Change::Same, *line)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            changes.push((Change::Same, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push((Change::Removed, old[i]));
+            i += 1;
+        } else {
+            changes.push((Change::Added, new[j]));
+            j += 1;
+        }
+    }
+    changes.extend(
+        before[before.len() - suffix..]
+            .iter()
+            .map(|line| (Change::Same, *line)),
+    );
+    changes
+}
+
+/// Changed lines of a rewrite as a unified diff without context
+pub fn diff(rewrite: &Rewrite) -> String {
+    let before: Vec<&str> = rewrite.before.lines().collect();
+    let after: Vec<&str> = rewrite.after.lines().collect();
+    let changes = diff_lines(&before, &after);
+
+    let path = rewrite.path.display();
+    let mut output = format!("--- {}\n+++ {}\n", path, path);
+    let (mut old_line, mut new_line) = (1, 1);
+    let mut k = 0;
+    while k < changes.len() {
+        if changes[k].0 == Change::Same {
+            old_line += 1;
+            new_line += 1;
+            k += 1;
+            continue;
+        }
+        let hunk: Vec<(Change, &str)> = changes[k..]
+            .iter()
+            .take_while(|(change, _)| *change != Change::Same)
+            .copied()
+            .collect();
+        let removed = hunk.iter().filter(|(c, _)| *c == Change::Removed).count();
+        let added = hunk.len() - removed;
+        // An empty side starts at the line before, as in `diff -U0`
+        let start = |line: usize, count: usize| if count == 0 { line - 1 } else { line };
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            start(old_line, removed),
+            removed,
+            start(new_line, added),
+            added
+        ));
+        for (change, line) in &hunk {
+            let sign = if *change == Change::Removed { '-' } else { '+' };
+            output.push_str(&format!("{}{}\n", sign, line));
+        }
+        old_line += removed;
+        new_line += added;
+        k += hunk.len();
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename(content: &str, from: &[&str], to: &str) -> Option<String> {
+        let from: Vec<String> = from.iter().map(|name| name.to_string()).collect();
+        rename_in_content(content, &from, to)
+    }
+
+    #[test]
+    fn renames_frontmatter_and_inline_tags() {
+        let content = "---\ntags: [project, rust]\n---\nSee #project and #Project/alpha, not `#project` or #projects.\n";
+        assert_eq!(
+            rename(content, &["project"], "work").unwrap(),
+            "---\ntags: [work, rust]\n---\nSee #work and #work/alpha, not `#project` or #projects.\n"
+        );
+        assert_eq!(rename(content, &["proj"], "work"), None);
+    }
+
+    #[test]
+    fn renames_nested_tags() {
+        let content = "---\ntags:\n  - a\n  - a/b\n  - a/b/c\n---\n#a/b/c #a/bc #a\n";
+        assert_eq!(
+            rename(content, &["a/b"], "x").unwrap(),
+            "---\ntags:\n  - a\n  - x\n  - x/c\n---\n#x/c #a/bc #a\n"
+        );
+        // The longest matching tag wins
+        assert_eq!(renamed("a/b/c", &["a".into(), "a/b".into()], "x"), Some("x/c".into()));
+    }
+
+    #[test]
+    fn merges_into_an_existing_tag() {
+        let content = "---\ntags: [draft, todo, wip]\n---\n#wip #todo\n";
+        assert_eq!(
+            rename(content, &["wip", "draft"], "todo").unwrap(),
+            "---\ntags: [todo]\n---\n#todo #todo\n"
+        );
+        // Hashtags only
+        assert_eq!(rename("Body #wip\n", &["wip"], "todo").unwrap(), "Body #todo\n");
+    }
+
+    #[test]
+    fn diffs_rewrites() {
+        let rewrite = Rewrite {
+            path: PathBuf::from("a.md"),
+            before: String::from("---\ntags: [wip]\n---\n\nsame\n#wip\nend\n"),
+            after: String::from("---\ntags: [todo]\n---\n\nsame\n#todo\nend\n"),
+        };
+        assert_eq!(
+            diff(&rewrite),
+            "--- a.md\n+++ a.md\n@@ -2,1 +2,1 @@\n-tags: [wip]\n+tags: [todo]\n@@ -6,1 +6,1 @@\n-#wip\n+#todo\n"
+        );
+
+        let rewrite = Rewrite {
+            path: PathBuf::from("b.md"),
+            before: String::from("a\nb\nc\n"),
+            after: String::from("a\nc\nd\n"),
+        };
+        assert_eq!(diff(&rewrite), "--- b.md\n+++ b.md\n@@ -2,1 +1,0 @@\n-b\n@@ -3,0 +3,1 @@\n+d\n");
+    }
+
+    #[test]
+    fn adds_and_removes_tags_ignoring_case() {
+        assert!(same_tag("#Rust", "rust"));
+        assert!(!same_tag("rust/async", "rust"));
+
+        let path = std::env::temp_dir().join(format!("noteorg-{}-tags.md", std::process::id()));
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        fs::write(&path, "---\ntags: ['#Rust', draft]\n---\nbody\n").unwrap();
+        assert!(!add_tags(&path, &names(&["rust", "DRAFT"])).unwrap());
+        assert!(add_tags(&path, &names(&["rust", "ideas"])).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "---\ntags: ['#Rust', draft, 'ideas']\n---\nbody\n");
+        assert!(remove_tags(&path, &names(&["RUST", "draft"])).unwrap());
+        assert!(!remove_tags(&path, &names(&["rust"])).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "---\ntags: ['ideas']\n---\nbody\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
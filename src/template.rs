@@ -0,0 +1,476 @@
+// Note templates
+//
+// A template is a markdown file from `~/.config/noteorg/templates/` whose
+// optional frontmatter describes the template itself:
+//
+//     ---
+//     description: Meeting notes
+//     category: work/meetings
+//     tags: [meeting]
+//     variables:
+//       project:
+//         prompt: Project name
+//         default: noteorg
+//       kind:
+//         choices: [standup, retro]
+//     ---
+//
+// The body supports `{{name}}` placeholders, `{{date}}`/`{{date:%Y-%m-%d}}`
+// and `{{time}}`/`{{time:%H:%M}}`, `{{cursor}}` marking where the editor
+// should start, and `{{#if name}}...{{else}}...{{/if}}` (or `#unless`)
+// sections rendered depending on whether the variable is set.
+
+use crate::{config, note, prompt};
+use chrono::format::{Item, StrftimeItems};
+use chrono::DateTime;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::{self, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Skeleton written by `note templates new`
+const TEMPLATE_SKELETON: &str = "---
+description:
+tags: []
+variables:
+  example:
+    prompt: Example variable
+    default: value
+---
+# {{title}}
+
+Created {{date:%Y-%m-%d}}{{#if example}} for {{example}}{{/if}}.
+
+{{cursor}}
+";
+
+/// A variable declared in the template frontmatter
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Variable {
+    #[serde(skip)]
+    pub name: String,
+    /// Question shown when prompting, defaults to the variable name
+    pub prompt: Option<String>,
+    pub default: Option<String>,
+    /// Allowed values, picked from a list when prompting
+    pub choices: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+struct TemplateHeader {
+    description: Option<String>,
+    category: Option<String>,
+    tags: Vec<String>,
+    variables: serde_yaml::Mapping,
+}
+
+#[derive(Debug)]
+pub struct Template {
+    pub name: String,
+    pub path: PathBuf,
+    pub description: Option<String>,
+    /// Category used when none is given on the command line
+    pub category: Option<String>,
+    /// Tags added to every note created from this template
+    pub tags: Vec<String>,
+    /// Declared variables, in declaration order
+    pub variables: Vec<Variable>,
+    pub body: String,
+}
+
+/// Values available while rendering
+pub struct Context {
+    pub now: DateTime<config::Timezone>,
+    pub variables: HashMap<String, String>,
+}
+
+/// A rendered template and the 1-based line of its `{{cursor}}`, if any
+pub struct Rendered {
+    pub text: String,
+    pub cursor_line: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Variable { name: String, format: Option<String> },
+    Cursor,
+    Condition { name: String, negate: bool, then: Vec<Node>, otherwise: Vec<Node> },
+}
+
+fn template_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Directory holding the user templates (`~/.config/noteorg/templates/`)
+pub fn templates_dir() -> io::Result<PathBuf> {
+    Ok(config::config_dir()?.join("templates"))
+}
+
+fn template_path(name: &str) -> io::Result<PathBuf> {
+    let dir = templates_dir()?;
+    Ok(if name.ends_with(".md") {
+        dir.join(name)
+    } else {
+        dir.join(format!("{}.md", name))
+    })
+}
+
+impl Template {
+    /// Read a template by name, with or without its `.md` extension
+    pub fn load(name: &str) -> io::Result<Self> {
+        let path = template_path(name)?;
+        let text = read_to_string(&path).map_err(|e| {
+            io::Error::new(e.kind(), format!("Cannot read template {:?}: {}", path, e))
+        })?;
+        Self::parse(name.trim_end_matches(".md"), &path, &text)
+    }
+
+    pub fn parse(name: &str, path: &Path, text: &str) -> io::Result<Self> {
+        let (frontmatter, body) = note::split_frontmatter(text);
+        let header: TemplateHeader = match frontmatter {
+            Some(yaml) if !yaml.trim().is_empty() => serde_yaml::from_str(yaml).map_err(|e| {
+                template_error(format!("Invalid template header in {:?}: {}", path, e))
+            })?,
+            _ => TemplateHeader::default(),
+        };
+
+        let variables = header
+            .variables
+            .into_iter()
+            .map(|(key, value)| {
+                let name = key.as_str().map(String::from).ok_or_else(|| {
+                    template_error(format!("Variable names must be strings in {:?}", path))
+                })?;
+                let mut variable: Variable = if value.is_null() {
+                    Variable::default()
+                } else {
+                    serde_yaml::from_value(value).map_err(|e| {
+                        template_error(format!("Invalid variable {:?} in {:?}: {}", name, path, e))
+                    })?
+                };
+                variable.name = name;
+                Ok(variable)
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let template = Self {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            description: header.description.filter(|d| !d.trim().is_empty()),
+            category: header.category,
+            tags: header.tags,
+            variables,
+            body: body.to_string(),
+        };
+        // Report syntax errors when loading rather than when creating a note
+        template.nodes()?;
+        Ok(template)
+    }
+
+    fn nodes(&self) -> io::Result<Vec<Node>> {
+        parse_nodes(&self.body).map_err(|e| template_error(format!("{} in {:?}", e, self.path)))
+    }
+
+    /// Values of the declared variables: taken from `provided`, otherwise
+    /// asked interactively when possible, otherwise their default
+    pub fn resolve_variables(
+        &self,
+        provided: &HashMap<String, String>,
+        interactive: bool,
+    ) -> io::Result<HashMap<String, String>> {
+        let mut values = provided.clone();
+        for variable in &self.variables {
+            if let Some(value) = provided.get(&variable.name) {
+                if !variable.choices.is_empty() && !variable.choices.contains(value) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "Invalid value {:?} for {}, expected one of: {}",
+                            value,
+                            variable.name,
+                            variable.choices.join(", ")
+                        ),
+                    ));
+                }
+                continue;
+            }
+            let value = if interactive {
+                let label = variable.prompt.as_deref().unwrap_or(&variable.name);
+                prompt::prompt_choice(label, &variable.choices, variable.default.as_deref())?
+            } else {
+                variable
+                    .default
+                    .clone()
+                    .or_else(|| variable.choices.first().cloned())
+                    .unwrap_or_default()
+            };
+            values.insert(variable.name.clone(), value);
+        }
+        Ok(values)
+    }
+
+    pub fn render(&self, context: &Context) -> io::Result<Rendered> {
+        // Built-in values are variables too, so that sections can test them
+        let mut variables = context.variables.clone();
+        for (name, format) in [("date", "%Y-%m-%d"), ("time", "%H:%M")] {
+            variables
+                .entry(name.to_string())
+                .or_insert_with(|| context.now.format(format).to_string());
+        }
+        let context = Context {
+            now: context.now,
+            variables,
+        };
+
+        let mut rendered = Rendered {
+            text: String::with_capacity(self.body.len()),
+            cursor_line: None,
+        };
+        render_nodes(&self.nodes()?, &context, &mut rendered)?;
+        Ok(rendered)
+    }
+}
+
+/// Names of the templates in the templates directory, sorted
+pub fn template_names() -> io::Result<Vec<String>> {
+    let dir = templates_dir()?;
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Write a skeleton template, failing if one with that name exists
+pub fn create_template(name: &str) -> io::Result<PathBuf> {
+    let path = template_path(name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| io::Write::write_all(&mut file, TEMPLATE_SKELETON.as_bytes()))
+        .map_err(|e| io::Error::new(e.kind(), format!("Cannot create {:?}: {}", path, e)))?;
+    Ok(path)
+}
+
+/// A `#if`/`#unless` section being parsed
+struct Section {
+    name: String,
+    negate: bool,
+    /// Nodes preceding the section
+    parent: Vec<Node>,
+    /// Nodes of the first branch, set once `else` is reached
+    then: Option<Vec<Node>>,
+}
+
+fn parse_nodes(text: &str) -> Result<Vec<Node>, String> {
+    let mut stack: Vec<Section> = vec![];
+    let mut current: Vec<Node> = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}").map(|end| start + end) else {
+            break;
+        };
+        if start > 0 {
+            current.push(Node::Text(rest[..start].to_string()));
+        }
+        let tag = rest[start + 2..end].trim();
+        rest = &rest[end + 2..];
+
+        if let Some(name) = tag.strip_prefix("#if ").or_else(|| tag.strip_prefix("#unless ")) {
+            stack.push(Section {
+                name: name.trim().to_string(),
+                negate: tag.starts_with("#unless"),
+                parent: std::mem::take(&mut current),
+                then: None,
+            });
+        } else if tag == "else" {
+            let Some(section) = stack.last_mut() else {
+                return Err(String::from("Template error: `else` outside of a section"));
+            };
+            if section.then.is_some() {
+                return Err(format!("Template error: second `else` in `{}` section", section.name));
+            }
+            section.then = Some(std::mem::take(&mut current));
+        } else if tag == "/if" || tag == "/unless" {
+            let Some(section) = stack.pop() else {
+                return Err(format!("Template error: `{}` without an opening section", tag));
+            };
+            if section.negate != (tag == "/unless") {
+                let opening = if section.negate { "#unless" } else { "#if" };
+                return Err(format!(
+                    "Template error: `{}` closes the `{} {}` section",
+                    tag, opening, section.name
+                ));
+            }
+            let (then, otherwise) = match section.then {
+                Some(then) => (then, std::mem::take(&mut current)),
+                None => (std::mem::take(&mut current), vec![]),
+            };
+            current = section.parent;
+            current.push(Node::Condition {
+                name: section.name,
+                negate: section.negate,
+                then,
+                otherwise,
+            });
+        } else if tag == "cursor" {
+            current.push(Node::Cursor);
+        } else {
+            let (name, format) = match tag.split_once(':') {
+                Some((name, format)) => (name.trim(), Some(format.to_string())),
+                None => (tag, None),
+            };
+            current.push(Node::Variable { name: name.to_string(), format });
+        }
+    }
+    if !rest.is_empty() {
+        current.push(Node::Text(rest.to_string()));
+    }
+
+    match stack.pop() {
+        Some(section) => Err(format!(
+            "Template error: unclosed `{}` section",
+            section.name
+        )),
+        None => Ok(current),
+    }
+}
+
+fn is_truthy(value: Option<&String>) -> bool {
+    value.is_some_and(|value| {
+        !value.is_empty() && !matches!(value.to_lowercase().as_str(), "false" | "no" | "0" | "off")
+    })
+}
+
+fn format_date(now: &DateTime<config::Timezone>, format: &str) -> io::Result<String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(template_error(format!("Invalid date format {:?}", format)));
+    }
+    Ok(now.format(format).to_string())
+}
+
+fn render_nodes(nodes: &[Node], context: &Context, output: &mut Rendered) -> io::Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.text.push_str(text),
+            Node::Cursor => {
+                output.cursor_line = Some(output.text.matches('\n').count() + 1);
+            }
+            Node::Condition { name, negate, then, otherwise } => {
+                let branch = if is_truthy(context.variables.get(name)) != *negate {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, context, output)?;
+            }
+            Node::Variable { name, format } => match (name.as_str(), format) {
+                ("date", format) => output.text.push_str(&format_date(
+                    &context.now,
+                    format.as_deref().unwrap_or("%Y-%m-%d"),
+                )?),
+                ("time", format) => output.text.push_str(&format_date(
+                    &context.now,
+                    format.as_deref().unwrap_or("%H:%M"),
+                )?),
+                (name, None) if context.variables.contains_key(name) => {
+                    output.text.push_str(&context.variables[name]);
+                }
+                // Unknown placeholders are kept as written
+                (name, format) => {
+                    output.text.push_str("{{");
+                    output.text.push_str(name);
+                    if let Some(format) = format {
+                        output.text.push(':');
+                        output.text.push_str(format);
+                    }
+                    output.text.push_str("}}");
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(body: &str, variables: &[(&str, &str)]) -> io::Result<Rendered> {
+        let template = Template::parse("test", Path::new("test.md"), body)?;
+        let now = DateTime::parse_from_rfc3339("2026-10-17T09:05:00+02:00").unwrap();
+        let now = now.with_timezone(&config::Timezone::Fixed(*now.offset()));
+        let variables = variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        template.render(&Context { now, variables })
+    }
+
+    fn text(body: &str, variables: &[(&str, &str)]) -> String {
+        render(body, variables).unwrap().text
+    }
+
+    #[test]
+    fn renders_variables_dates_and_unknown_placeholders() {
+        assert_eq!(
+            text("# {{title}} {{date}} {{time}} {{date:%d/%m}} {{other}}", &[("title", "Hi")]),
+            "# Hi 2026-10-17 09:05 17/10 {{other}}"
+        );
+    }
+
+    #[test]
+    fn renders_sections() {
+        let body = "{{#if project}}for {{project}}{{else}}no project{{/if}}";
+        assert_eq!(text(body, &[("project", "noteorg")]), "for noteorg");
+        assert_eq!(text(body, &[("project", "no")]), "no project");
+        assert_eq!(text(body, &[]), "no project");
+        assert_eq!(text("{{#unless done}}todo{{/unless}}", &[]), "todo");
+        assert_eq!(
+            text("{{#if a}}{{#if b}}ab{{else}}a{{/if}}{{/if}}", &[("a", "1")]),
+            "a"
+        );
+    }
+
+    #[test]
+    fn built_in_values_are_set_for_sections() {
+        assert_eq!(text("{{#if date}}dated{{/if}}", &[]), "dated");
+        assert_eq!(text("{{#unless time}}untimed{{/unless}}", &[]), "");
+    }
+
+    #[test]
+    fn reports_the_cursor_line() {
+        let rendered = render("# {{title}}\n\n{{cursor}}\n", &[("title", "Hi")]).unwrap();
+        assert_eq!(rendered.text, "# Hi\n\n\n");
+        assert_eq!(rendered.cursor_line, Some(3));
+    }
+
+    #[test]
+    fn rejects_malformed_sections() {
+        for body in [
+            "{{#if x}}text{{/unless}}",
+            "{{#unless x}}text{{/if}}",
+            "{{#if x}}text",
+            "text{{/if}}",
+            "{{else}}",
+            "{{#if x}}a{{else}}b{{else}}c{{/if}}",
+        ] {
+            assert!(render(body, &[]).is_err(), "{:?} was accepted", body);
+        }
+    }
+}